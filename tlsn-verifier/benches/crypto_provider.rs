@@ -0,0 +1,45 @@
+//! Compares verifying a presentation with one shared `CryptoProvider` against building
+//! `CryptoProvider::default()` for every request, as `verifier.rs` did before the provider was
+//! shared. Uses the sample presentation in `samples/verify-proof`.
+//! Run with `cargo bench --bench crypto_provider`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::hint::black_box;
+use tlsn_core::presentation::Presentation;
+use tlsn_core::CryptoProvider;
+
+/// Sample `/verify-proof` request: request line and headers, an empty line, then the JSON body
+const SAMPLE_REQUEST: &str = include_str!("../samples/verify-proof/verify-proof-request.http");
+
+/// Returns the bincode-encoded presentation of the sample request
+fn sample_presentation_bytes() -> Vec<u8> {
+    let (_, body) = SAMPLE_REQUEST.split_once("\n\n").expect("sample request has no body");
+    let json: serde_json::Value = serde_json::from_str(body).expect("sample body is not JSON");
+    hex::decode(json["data"].as_str().expect("sample body has no data")).expect("sample data is not hex")
+}
+
+fn decode(bytes: &[u8]) -> Presentation {
+    bincode::deserialize(bytes).expect("sample presentation does not decode")
+}
+
+fn crypto_provider(c: &mut Criterion) {
+    let bytes = sample_presentation_bytes();
+    let shared = CryptoProvider::default();
+    let mut group = c.benchmark_group("crypto_provider");
+    group.bench_function("construct", |b| b.iter(|| black_box(CryptoProvider::default())));
+    // Decoding is left out of the measurement; it is the same for both
+    group.bench_function("verify_shared", |b| {
+        b.iter_batched(|| decode(&bytes), |p| black_box(p.verify(&shared)), BatchSize::SmallInput)
+    });
+    group.bench_function("verify_per_request", |b| {
+        b.iter_batched(
+            || decode(&bytes),
+            |p| black_box(p.verify(&CryptoProvider::default())),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, crypto_provider);
+criterion_main!(benches);
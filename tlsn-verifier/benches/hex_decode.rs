@@ -0,0 +1,45 @@
+//! Measures `decode_hex_skipping_whitespace` on large presentations, against the previous
+//! approach of collecting the non-whitespace characters into a `String` and calling `hex::decode`.
+//! Run with `cargo bench --bench hex_decode`.
+
+// The module's unit tests are compiled without the test harness here, leaving their imports unused
+#[allow(unused_imports)]
+#[path = "../src/hex_decode.rs"]
+mod hex_decode;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hex_decode::decode_hex_skipping_whitespace;
+use std::hint::black_box;
+
+/// Hex-encodes `len` bytes, broken into lines of 64 characters as in pasted presentations
+fn presentation_hex(len: usize) -> String {
+    let bytes: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+    let hex = hex::encode(bytes);
+    let mut wrapped = String::with_capacity(hex.len() + hex.len() / 64 + 1);
+    for line in hex.as_bytes().chunks(64) {
+        wrapped.push_str(std::str::from_utf8(line).unwrap());
+        wrapped.push('\n');
+    }
+    wrapped
+}
+
+fn hex_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hex_decode");
+    for len in [64 * 1024, 1024 * 1024, 8 * 1024 * 1024] {
+        let data = presentation_hex(len);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::new("skipping_whitespace", len), &data, |b, data| {
+            b.iter(|| decode_hex_skipping_whitespace(black_box(data)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("collect_then_decode", len), &data, |b, data| {
+            b.iter(|| {
+                let compact: String = black_box(data).chars().filter(|c| !c.is_whitespace()).collect();
+                hex::decode(compact).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, hex_decode);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+use crate::auth::ApiKeyId;
+use crate::config;
+use crate::types::VerificationSummary;
+use actix_web::http::header::HeaderMap;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Logger;
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Log target of the access log lines, so they are not mixed with other `log` output
+pub(crate) const ACCESS_LOG_TARGET: &str = "access_log";
+
+/// Log target of the verification summary lines
+pub(crate) const SUMMARY_LOG_TARGET: &str = "verification_summary";
+
+/// Header carrying the caller's request id, echoed into the access log
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// One JSON object per request. The `%{..}xi`/`%{..}xo` fields are filled by the replacements
+/// registered in `access_logger`, which render them as JSON values (quoted and escaped).
+const JSON_FORMAT: &str = r#"{"time":"%t","remote_addr":"%a","method":%{method}xi,"path":%{path}xi,"status":%s,"latency_ms":%D,"response_bytes":%b,"request_id":%{request_id}xi,"api_key_id":%{api_key_id}xo}"#;
+
+/// Actix's default (combined-like) plain text format
+const COMMON_FORMAT: &str = r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T"#;
+
+/// Forwards access log and verification summary lines to `tracing`, whose subscriber writes them
+/// to stdout unchanged; all other `log` records are dropped
+struct AccessLogSink;
+
+impl Log for AccessLogSink {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == ACCESS_LOG_TARGET || metadata.target() == SUMMARY_LOG_TARGET
+    }
+
+    fn log(&self, record: &Record) {
+        match record.target() {
+            ACCESS_LOG_TARGET => tracing::info!(target: ACCESS_LOG_TARGET, "{}", record.args()),
+            SUMMARY_LOG_TARGET => tracing::info!(target: SUMMARY_LOG_TARGET, "{}", record.args()),
+            _ => {}
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static ACCESS_LOG_SINK: AccessLogSink = AccessLogSink;
+
+/// Installs the `log` sink if `TLSN_VERIFIER_ACCESS_LOG` or
+/// `TLSN_VERIFIER_VERIFICATION_SUMMARY_LOG` is enabled.
+/// Fails if another `log` backend is already installed.
+pub fn init_access_log() -> Result<(), String> {
+    if !config::get_access_log() && !config::get_verification_summary_log() {
+        return Ok(());
+    }
+    log::set_logger(&ACCESS_LOG_SINK).map_err(|e| format!("Cannot install access log sink: {}", e))?;
+    log::set_max_level(LevelFilter::Info);
+    Ok(())
+}
+
+/// Builds the access log middleware with the format from `TLSN_VERIFIER_ACCESS_LOG_FORMAT`:
+/// `json` (default), `common`, or a custom actix `Logger` format string, which may also use the
+/// `%{method}xi`, `%{path}xi`, `%{request_id}xi` and `%{api_key_id}xo` fields
+pub fn access_logger() -> Logger {
+    let format = config::get_access_log_format();
+    let format = match format.to_lowercase().as_str() {
+        "json" => JSON_FORMAT.to_string(),
+        "common" => COMMON_FORMAT.to_string(),
+        _ => format,
+    };
+    Logger::new(&format)
+        .log_target(ACCESS_LOG_TARGET)
+        .custom_request_replace("method", |req: &ServiceRequest| json_string(req.method().as_str()))
+        .custom_request_replace("path", |req: &ServiceRequest| json_string(req.path()))
+        .custom_request_replace("request_id", |req: &ServiceRequest| {
+            request_id(req.headers()).map_or_else(|| "null".to_string(), json_string)
+        })
+        .custom_response_replace("api_key_id", |res: &ServiceResponse| {
+            ApiKeyId::from_request(res.request()).map_or_else(|| "null".to_string(), |id| json_string(&id.0))
+        })
+}
+
+/// Returns the caller's request id (`X-Request-Id`), if present and valid text
+pub fn request_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok())
+}
+
+/// Writes a verification summary as one JSON line, if `TLSN_VERIFIER_VERIFICATION_SUMMARY_LOG` is enabled
+pub fn log_verification_summary(summary: &VerificationSummary) {
+    if !config::get_verification_summary_log() {
+        return;
+    }
+    if let Ok(line) = serde_json::to_string(summary) {
+        log::info!(target: SUMMARY_LOG_TARGET, "{}", line);
+    }
+}
+
+/// Renders `value` as a quoted, escaped JSON string
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+}
@@ -1,83 +1,321 @@
 // Imports the key material and utility types for attestation
 use crate::types::{AttestationError, SignedAttestation};
 use crate::types::*;
+use serde::Serialize;
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::config;
 use crate::utils;
-use crate::tappd_service;
-use crate::key_manager::{try_get_key_material};
+use crate::quote;
+use crate::quote_queue;
+use crate::key_manager::{try_get_key_material, try_get_operator_key};
+use crate::measurement_policy::try_get_measurement_policy;
+use crate::metrics::{self, AttestationMetrics};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+/// A quote kept for reuse until it expires
+struct CachedQuote {
+    response: GetQuoteResponse,
+    expires_at: Instant,
+}
+
+/// Quotes cached by `(verifying key, report_data)`, so a different key never reuses a quote
+static QUOTE_CACHE: Lazy<Mutex<HashMap<(String, String), CachedQuote>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Removes all cached quotes, returning how many were evicted
+pub fn clear_quote_cache() -> usize {
+    QUOTE_CACHE.lock().unwrap().drain().count()
+}
+
+/// Returns a cached quote for `key` that has not expired yet
+fn cached_quote(key: &(String, String)) -> Option<GetQuoteResponse> {
+    let now = Instant::now();
+    QUOTE_CACHE
+        .lock()
+        .unwrap()
+        .get(key)
+        .filter(|cached| cached.expires_at > now)
+        .map(|cached| cached.response.clone())
+}
+
+/// Caches a quote for `ttl`, purging expired quotes first
+fn cache_quote(key: (String, String), response: &GetQuoteResponse, ttl: Duration) {
+    let now = Instant::now();
+    let mut cache = QUOTE_CACHE.lock().unwrap();
+    cache.retain(|_, cached| cached.expires_at > now);
+    cache.insert(key, CachedQuote { response: response.clone(), expires_at: now + ttl });
+}
 
 /// Connects to the TDX quote provider (`tappd`) via Unix socket,
 /// sends a custom attestation request with the report_data derived from the key,
-/// and returns the parsed attestation quote as a `GetQuoteResponse`
-pub async fn read_attestation_report(data : &str) -> Result<GetQuoteResponse, AttestationError> {
+/// and returns the parsed attestation quote as a `GetQuoteResponse` together with the time
+/// spent obtaining it (including time queued for the provider)
+pub async fn read_attestation_report(
+    data : &str,
+    hash_algorithm: ReportDataHashAlgorithm,
+) -> Result<(GetQuoteResponse, Duration), AttestationError> {
     // Ensure key material has been initialized
-    let report_data = utils::prepare_report_data(&data);
-    println!("[read_attestation_report] Report data prepared successfully: {}", report_data);
+    let report_data = utils::prepare_report_data(&data, hash_algorithm);
+    debug!(report_data = %report_data, "Report data prepared");
+
+    // Serve an identical quote from memory while it is fresh (if `TLSN_VERIFIER_QUOTE_CACHE_SECS` is set).
+    // The verifying key is part of the cache key, so a different key material invalidates entries.
+    let cache_ttl = config::get_quote_cache_secs().map(Duration::from_secs);
+    let cache_key = cache_ttl.map(|_| {
+        let verifying_key = try_get_key_material().map(|key| key.encode_verify_key()).unwrap_or_default();
+        (verifying_key, report_data.clone())
+    });
+    if let Some(cached) = cache_key.as_ref().and_then(cached_quote) {
+        debug!("Using cached quote");
+        return Ok((cached, Duration::ZERO));
+    }
     // Construct the evidence
     let custom_evidence = json!({
         "report_data": report_data,  // 64-byte SHA512 hash (hex)
         "hash_algorithm": "raw"  // Request raw hashing algorithm
     });
-    println!("[read_attestation_report] Custom evidence constructed: {}", custom_evidence);
+    trace!(evidence = %custom_evidence, "Quote request constructed");
+
+    // Send the request through the quote queue (serializing provider access) and await the body
+    let started = Instant::now();
+    let body_bytes = quote_queue::request_quote(&custom_evidence.to_string()).await?;
+    let quote_duration = started.elapsed();
+    debug!(quote_ms = metrics::as_millis_f64(quote_duration), "Quote received from tappd");
 
-    // Send the request to the tappd socket and await response
-    let res = tappd_service::send_quote_request(&custom_evidence.to_string()).await.map_err(|e| {
+    // Parse the body into a `GetQuoteResponse` structure
+    let parsed: GetQuoteResponse = serde_json::from_slice(&body_bytes).map_err(|e| {
         AttestationError {
-            message: format!("Tappd Service Error: {}", e.message),
+            code: None,
+            message: format!("Failed to parse GetQuoteResponse: {}", e),
         }
     })?;
-    println!("[read_attestation_report] Response received from tappd service");
+    trace!("GetQuoteResponse parsed");
 
-    // Read the response body bytes
-    let body_bytes = hyper::body::to_bytes(res.into_body()).await.map_err(|e| {
-        AttestationError {
-            message: format!("Failed to read response body: {}", e),
-        }
+    // Guard against provider bugs (e.g. a stale quote): the quote must embed what we asked for
+    check_quote_report_data(&parsed.quote, &report_data)?;
+    if let (Some(key), Some(ttl)) = (cache_key, cache_ttl) {
+        cache_quote(key, &parsed, ttl);
+    }
+    Ok((parsed, quote_duration))
+}
+
+/// Checks that a quote's report_data equals the requested report_data (hex, optionally
+/// `0x`-prefixed), zero-padded to 64 bytes for shorter hashes as the provider does.
+/// Fails with `REPORT_DATA_MISMATCH` otherwise.
+fn check_quote_report_data(quote_hex: &str, requested_hex: &str) -> Result<(), AttestationError> {
+    let requested = hex::decode(requested_hex.strip_prefix("0x").unwrap_or(requested_hex))
+        .map_err(|e| AttestationError::with_code("REPORT_DATA_MISMATCH", format!("Invalid requested report_data: {}", e)))?;
+    let embedded = quote::report_data_from_hex_quote(quote_hex).map_err(|e| {
+        AttestationError::with_code("REPORT_DATA_MISMATCH", format!("Cannot read report_data from quote: {}", e))
     })?;
-    println!("[read_attestation_report] Response body read successfully");
+    let matches = requested.len() <= embedded.len()
+        && embedded[..requested.len()] == requested[..]
+        && embedded[requested.len()..].iter().all(|b| *b == 0);
+    if !matches {
+        return Err(AttestationError::with_code(
+            "REPORT_DATA_MISMATCH",
+            format!(
+                "Quote report_data {} does not match the requested {}",
+                hex::encode(embedded),
+                hex::encode(&requested)
+            ),
+        ));
+    }
+    Ok(())
+}
 
-    // Parse the body into a `GetQuoteResponse` structure
-    let parsed: GetQuoteResponse = serde_json::from_slice(&body_bytes).map_err(|e| {
-        AttestationError {
-            message: format!("Failed to parse GetQuoteResponse: {}", e),
+/// Signature over a hex-encoded payload together with the key material needed to check it
+pub struct PayloadSignature {
+    pub signature: String,              // Hex-encoded signature
+    pub encoded_key: String,            // Hex-encoded verifying key matching the signature
+    pub recovery_id: Option<u8>,        // Recovery id (keccak256 mode only)
+    pub signer_address: Option<String>, // Ethereum address of the signer (keccak256 mode only)
+}
+
+/// Signs a hex-encoded payload with the given scheme and returns the matching verifying key
+pub fn sign_payload(
+    key_material: &KeyMaterial,
+    message_hex: &str,
+    signing_mode: SigningMode,
+) -> Result<PayloadSignature, AttestationError> {
+    match signing_mode {
+        SigningMode::P256Sha256 | SigningMode::Ed25519 => {
+            if signing_mode != SigningMode::for_scheme(key_material.scheme) {
+                return Err(AttestationError {
+                    code: None,
+                    message: format!(
+                        "Signing mode {:?} does not match the key's scheme {:?}",
+                        signing_mode, key_material.scheme
+                    ),
+                });
+            }
+            Ok(PayloadSignature {
+                signature: utils::sign_message(key_material, message_hex),
+                encoded_key: key_material.encode_verify_key(),
+                recovery_id: None,
+                signer_address: None,
+            })
+        }
+        SigningMode::Keccak256Recoverable => {
+            let (signature, recovery_id) = utils::sign_message_keccak256(key_material, message_hex)?;
+            Ok(PayloadSignature {
+                signature,
+                encoded_key: hex::encode(key_material.evm_public_key_bytes()?),
+                recovery_id: Some(recovery_id),
+                signer_address: Some(key_material.evm_address()?),
+            })
         }
+    }
+}
+
+/// Bundles a verification result with its attestation (quote, event log, certificate chain and
+/// signature) and signs SHA-256 of the JSON-serialized contents, so the whole bundle can be
+/// archived and verified independently later
+pub fn build_attestation_bundle(
+    verification: Result<VerificationResult, VerificationError>,
+    attestation: SignedAttestation,
+) -> Result<AttestationBundle, AttestationError> {
+    let key_material = try_get_key_material().ok_or_else(|| AttestationError {
+        code: None,
+        message: "Key material not initialized".to_string(),
     })?;
-    println!("[read_attestation_report] GetQuoteResponse parsed successfully");
-    Ok(parsed)
+    let event_log = attestation.event_log.clone().unwrap_or_default();
+    let contents = BundleContents { verification, attestation, event_log };
+
+    // Sign the digest of the canonical JSON serialization of the contents
+    let digest = Sha256::digest(serde_json::to_vec(&contents)?);
+    let digest_hex = hex::encode(digest);
+    let signing_mode = config::get_signing_mode();
+    let signed = sign_payload(&key_material, &digest_hex, signing_mode)?;
+    debug!(digest = %digest_hex, "Bundle digest signed");
+
+    Ok(AttestationBundle {
+        bundle_version: 1,
+        contents,
+        bundle_digest_hex_encoded: digest_hex,
+        bundle_signature_hex_encoded: signed.signature,
+        bundle_signing_mode: signing_mode,
+        bundle_algorithm: signing_mode.algorithm(),
+        bundle_verifying_key_hex_encoded: signed.encoded_key,
+    })
+}
+
+/// Format version of the payloads wrapped in a `SignedEnvelope`; bump it when the shape of the
+/// `/verify-proof` or `/attestation` responses changes incompatibly
+pub const RESPONSE_SCHEMA_VERSION: u32 = 1;
+
+/// The part of an envelope covered by its signature
+#[derive(Serialize)]
+struct VersionedPayload<'a, T> {
+    schema_version: u32,
+    payload: &'a T,
+}
+
+/// Wraps a response in a signed envelope. The signature covers SHA-256 of the JSON serialization
+/// of `{"schema_version": .., "payload": ..}`, so the version cannot be changed after signing
+pub fn build_envelope<T: Serialize>(payload: T) -> Result<SignedEnvelope<T>, AttestationError> {
+    let key_material = try_get_key_material().ok_or_else(|| AttestationError {
+        code: None,
+        message: "Key material not initialized".to_string(),
+    })?;
+    let versioned = VersionedPayload { schema_version: RESPONSE_SCHEMA_VERSION, payload: &payload };
+    let digest_hex = hex::encode(Sha256::digest(serde_json::to_vec(&versioned)?));
+    let signing_mode = config::get_signing_mode();
+    let signed = sign_payload(&key_material, &digest_hex, signing_mode)?;
+
+    Ok(SignedEnvelope {
+        schema_version: RESPONSE_SCHEMA_VERSION,
+        payload,
+        signature: EnvelopeSignature {
+            digest_hex_encoded: digest_hex,
+            signature_hex_encoded: signed.signature,
+            signing_mode,
+            algorithm: signing_mode.algorithm(),
+            verifying_key_hex_encoded: signed.encoded_key,
+        },
+    })
 }
 
 /// Combines the attestation report with a digital signature and verifying key
-/// to create a `SignedAttestation` which can be sent for remote verification
-pub async fn get_attestation_report_with_signature(data: &str) -> Result<SignedAttestation, AttestationError> {
+/// to create a `SignedAttestation` which can be sent for remote verification.
+/// `data_description` describes the pre-hash input of report_data for relying parties.
+pub async fn get_attestation_report_with_signature(
+    data: &str,
+    data_description: &str,
+) -> Result<SignedAttestation, AttestationError> {
+    let started = Instant::now();
     // Ensure key material is available
     let key_material = try_get_key_material().ok_or_else(|| AttestationError {
+        code: None,
         message: "Key material not initialized".to_string(),
     })?;
-    println!("[get_attestation_report_with_signature] Key material initialized successfully");
-
     // Fetch the attestation report from tappd
-    let report = read_attestation_report(data).await?;
-    println!("[get_attestation_report_with_signature] Attestation report fetched successfully");
+    let hash_algorithm = config::get_report_data_hash_algorithm();
+    let (report, quote_duration) = read_attestation_report(data, hash_algorithm).await?;
     let report_data = report.quote;
-    println!("[get_attestation_report_with_signature] Report data: {}", report_data);
+
+    // Enforce the enclave measurement policy (if configured) on the measurements signed in the
+    // quote itself, before signing anything
+    if let Some(policy) = try_get_measurement_policy() {
+        let measurements = quote::decode_hex_quote(&report_data)
+            .and_then(|quote| quote::measurements_from_quote(&quote))
+            .map_err(|e| AttestationError {
+                code: None,
+                message: format!("Failed to read measurements from quote: {}", e),
+            })?;
+        policy.check(&measurements)?;
+        debug!("Measurements allowed by policy");
+    }
 
     // Convert the report data to hex so it can be signed
     let report_data_hex: String = utils::encode_message_hex(&report_data);
-    println!("[get_attestation_report_with_signature] Report data hex: {}", report_data_hex);
 
-    // Sign the hex-encoded attestation report
-    let signature = utils::sign_message(&key_material, &report_data_hex);
-    println!("[get_attestation_report_with_signature] Signature generated successfully: {}", signature);
+    // Sign the hex-encoded attestation report with the configured scheme
+    let signing_mode = config::get_signing_mode();
+    let PayloadSignature { signature, encoded_key, recovery_id, signer_address } =
+        sign_payload(&key_material, &report_data_hex, signing_mode)?;
+    trace!(signature = %signature, "Quote signature generated");
+    debug!(verifying_key = %encoded_key, signing_mode = ?signing_mode, "Quote signed");
+
+    // Countersign the same message with the operator's offline key (if configured), always P-256
+    let (operator_signature_hex, operator_verifying_key_hex, operator_algorithm) = match try_get_operator_key() {
+        Some(operator_key) => (
+            Some(utils::sign_message(operator_key, &report_data_hex)),
+            Some(operator_key.encode_verify_key()),
+            Some(SigningMode::P256Sha256.algorithm()),
+        ),
+        None => (None, None, None),
+    };
 
-    // Get the verifying key in hex format
-    let encoded_key = key_material.encode_verify_key();
-    println!("[get_attestation_report_with_signature] Verifying key encoded successfully: {}", encoded_key);
+    // Record the elapsed time and memory of this attestation
+    let attestation_metrics = AttestationMetrics {
+        quote_ms: metrics::as_millis_f64(quote_duration),
+        attestation_ms: metrics::as_millis_f64(started.elapsed()),
+        resident_memory_bytes: metrics::resident_memory_bytes(),
+    };
+    attestation_metrics.record();
     // Construct the signed attestation payload
     Ok(SignedAttestation {
         quote: report_data,                                 // Raw quote data (still hex)
+        event_log: Some(report.event_log),                  // Event log returned with the quote
         signature_hex_encoded: signature,                   // Signature over quote
         verifying_key_hex_encoded: encoded_key,             // Public key used to sign
         verifying_key_certificate_chain: key_material.certificate_chain.clone(), // Optional certificate chain
+        scheme: Some(key_material.scheme),                  // Key type of the enclave key
+        signing_mode,                                       // Scheme used to sign
+        algorithm: Some(signing_mode.algorithm()),          // Exact algorithm of the signature
+        report_data_hash_algorithm: hash_algorithm,         // Hash used for report_data
+        report_data_input: data_description.to_string(),    // Pre-hash input of report_data
+        recovery_id,                                        // Recovery id for `ecrecover`
+        signer_address,                                     // Ethereum address of the signer
+        operator_signature_hex,                             // Second signature by the operator key
+        operator_verifying_key_hex,                         // Operator key used for the second signature
+        operator_algorithm,                                 // Algorithm of the second signature
+        metrics: Some(attestation_metrics),                 // Timings (only exposed on `?debug=true`)
     })
     
 }
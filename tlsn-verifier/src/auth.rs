@@ -1,9 +1,83 @@
 use actix_web::body::BoxBody;
-use actix_web::{dev::ServiceRequest, Error, HttpResponse};
+use actix_web::{dev::ServiceRequest, Error, HttpMessage, HttpRequest, HttpResponse};
 use actix_web::dev::{Service, Transform};
 use futures_util::future::{ok, Ready, LocalBoxFuture};
+use once_cell::sync::OnceCell;
+#[allow(deprecated)] // Deprecated in ring 0.17 without a replacement; still constant time
+use ring::constant_time::verify_slices_are_equal;
+use sha2::{Digest, Sha256};
 use std::rc::Rc;
 use crate::config;
+use tracing::info;
+
+/// Paths served without an API key because they only expose public material
+const PUBLIC_PATHS: [&str; 1] = ["/.well-known/did.json"];
+
+/// Path prefixes that change the verifier's state and only accept admin keys
+const ADMIN_PATHS: [&str; 2] = ["/admin", "/rotate-key"];
+
+/// An accepted API key, kept as its identifier and the SHA-256 digest of the key
+struct AcceptedKey {
+    id: String,
+    digest: [u8; 32],
+}
+
+impl AcceptedKey {
+    /// Converts configured `(name, key)` pairs
+    fn from_config(keys: Vec<(Option<String>, String)>) -> Vec<Self> {
+        keys.into_iter()
+            .map(|(name, key)| AcceptedKey {
+                id: api_key_id(name.as_deref(), &key),
+                digest: Sha256::digest(key.as_bytes()).into(),
+            })
+            .collect()
+    }
+}
+
+/// Client keys (`TLSN_VERIFIER_API_KEYS`) and admin keys (`TLSN_VERIFIER_ADMIN_API_KEYS`)
+struct ApiKeys {
+    client: Vec<AcceptedKey>,
+    admin: Vec<AcceptedKey>,
+}
+
+/// API keys, loaded once at startup
+static API_KEYS: OnceCell<ApiKeys> = OnceCell::new();
+
+/// Loads the accepted client and admin keys, so requests do not re-read the environment.
+/// Panics if neither `TLSN_VERIFIER_API_KEYS` nor `TLSN_VERIFIER_API_KEY` yields a key.
+pub fn init_api_keys() {
+    let keys = api_keys();
+    info!(client_keys = keys.client.len(), admin_keys = keys.admin.len(), "Loaded API keys");
+}
+
+/// Returns the keys loaded at startup, loading them on first use if `init_api_keys` was not called
+fn api_keys() -> &'static ApiKeys {
+    API_KEYS.get_or_init(|| ApiKeys {
+        client: AcceptedKey::from_config(config::get_api_keys()),
+        admin: AcceptedKey::from_config(config::get_admin_api_keys()),
+    })
+}
+
+/// Identifier of the API key a request was authorized with, stored in the request extensions
+/// so routes and the access log can tell clients apart: the key's configured name, or a short
+/// SHA-256 fingerprint for unnamed keys, so the key itself is never logged.
+#[derive(Clone, Debug)]
+pub struct ApiKeyId(pub String);
+
+impl ApiKeyId {
+    /// Returns the identifier of the key that authorized `req`, if any
+    pub fn from_request(req: &HttpRequest) -> Option<Self> {
+        req.extensions().get::<ApiKeyId>().cloned()
+    }
+}
+
+/// Returns the identifier of a key: its name, or else the first 8 bytes of its SHA-256 digest, hex-encoded
+pub fn api_key_id(name: Option<&str>, api_key: &str) -> String {
+    match name {
+        Some(name) => name.to_string(),
+        None => hex::encode(&Sha256::digest(api_key.as_bytes())[..8]),
+    }
+}
 
 /// Middleware struct for API key-based authorization
 pub struct ApiKeyAuth;
@@ -52,15 +126,30 @@ where
 
     /// Handles the incoming request with API key authentication
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        // Retrieve expected API key from config
-        let api_key = config::get_api_key();
+        // Admin routes accept only admin keys and are never opened by the public paths
+        let is_admin_path = ADMIN_PATHS.iter().any(|prefix| is_under_prefix(req.path(), prefix));
+        let keys = api_keys();
+        let accepted_keys = if is_admin_path { &keys.admin } else { &keys.client };
+        let header_names = config::get_api_key_headers();
+
+        // Take the key from the first accepted header present (in configured order) and compare
+        // it to every accepted key in constant time, so the match position is not leaked
+        let presented = header_names.iter().find_map(|name| extract_api_key(&req, name));
+        let matched_key = presented.and_then(|presented| match_api_key(presented, accepted_keys));
+        let key_matched = matched_key.is_some();
+        let authorized = key_matched
+            || (!is_admin_path
+                && (PUBLIC_PATHS.contains(&req.path())
+                    || config::get_public_paths().iter().any(|prefix| is_under_prefix(req.path(), prefix))));
+        // A valid client key on an admin route is authenticated but lacks the scope
+        let forbidden = !authorized
+            && is_admin_path
+            && presented.is_some_and(|presented| match_api_key(presented, &keys.client).is_some());
 
-        // Extract "x-api-key" header and compare it to expected key
-        let authorized = req
-            .headers()
-            .get("x-api-key")
-            .and_then(|v| v.to_str().ok())
-            .map_or(false, |key| key == api_key);
+        // Record which key authorized the request for routes and the access log
+        if let Some(id) = matched_key {
+            req.extensions_mut().insert(ApiKeyId(id));
+        }
 
         // Clone the service so it can be used inside async block
         let srv = self.service.clone();
@@ -71,6 +160,10 @@ where
                 // If key matches, forward request to inner service
                 let res = srv.call(req).await?;
                 Ok(res.map_into_boxed_body())
+            } else if forbidden {
+                // If a client key was presented on an admin route, return 403 Forbidden response
+                let res = req.into_response(HttpResponse::Forbidden().finish());
+                Ok(res.map_into_boxed_body())
             } else {
                 // If unauthorized, return 401 Unauthorized response
                 let res = req.into_response(HttpResponse::Unauthorized().finish());
@@ -79,3 +172,74 @@ where
         })
     }
 }
+
+/// Compares `presented` to every key in `accepted_keys` and returns the identifier of the matching
+/// key, if any. The SHA-256 digests are compared in constant time; since they all have the same
+/// length, neither the key's length nor the match position is leaked.
+#[allow(deprecated)]
+fn match_api_key(presented: &str, accepted_keys: &[AcceptedKey]) -> Option<String> {
+    let digest = Sha256::digest(presented.as_bytes());
+    accepted_keys.iter().fold(None, |matched, key| {
+        let is_match = verify_slices_are_equal(&digest, &key.digest).is_ok();
+        matched.or(is_match.then(|| key.id.clone()))
+    })
+}
+
+/// Returns whether `path` is `prefix` or below it, matching whole path segments only,
+/// so `/health` covers `/health` and `/health/ready` but not `/healthcheck`
+fn is_under_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path.strip_prefix(prefix)
+        .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Extracts the API key from the given header of the request.
+/// For `Authorization`, only the `Bearer <key>` scheme is accepted.
+fn extract_api_key<'a>(req: &'a ServiceRequest, header_name: &str) -> Option<&'a str> {
+    let value = req.headers().get(header_name)?.to_str().ok()?.trim();
+    if header_name.eq_ignore_ascii_case("authorization") {
+        let (scheme, key) = value.split_once(' ')?;
+        return scheme.eq_ignore_ascii_case("bearer").then(|| key.trim());
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_admin_path(path: &str) -> bool {
+        ADMIN_PATHS.iter().any(|prefix| is_under_prefix(path, prefix))
+    }
+
+    #[test]
+    fn admin_paths_cover_admin_routes_only() {
+        assert!(is_admin_path("/admin/reload-policies"));
+        assert!(is_admin_path("/admin/cache/invalidate"));
+        assert!(is_admin_path("/admin/compare-key"));
+        assert!(is_admin_path("/rotate-key"));
+        assert!(!is_admin_path("/administrator"));
+        assert!(!is_admin_path("/verify-proof"));
+        assert!(!is_admin_path("/health"));
+    }
+
+    #[test]
+    fn match_api_key_returns_matching_key_id() {
+        let keys = AcceptedKey::from_config(vec![
+            (Some("ops".to_string()), "admin-key".to_string()),
+            (None, "other".to_string()),
+        ]);
+        assert_eq!(match_api_key("admin-key", &keys), Some("ops".to_string()));
+        assert_eq!(match_api_key("other", &keys), Some(api_key_id(None, "other")));
+        assert_eq!(match_api_key("client-key", &keys), None);
+        assert_eq!(match_api_key("admin-key", &[]), None);
+    }
+
+    #[test]
+    fn keys_of_other_lengths_do_not_match() {
+        let keys = AcceptedKey::from_config(vec![(None, "admin-key".to_string())]);
+        for presented in ["", "admin", "admin-key ", "admin-key-2", "ADMIN-KEY"] {
+            assert_eq!(match_api_key(presented, &keys), None, "{:?}", presented);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::config;
+use crate::types::BatchVerificationResponse;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Processing state of a stored batch
+#[derive(Clone)]
+pub enum BatchState {
+    Pending,                                 // Submitted asynchronously, still being verified
+    Complete(Arc<BatchVerificationResponse>), // Verified and attested
+}
+
+/// A batch kept for later retrieval
+struct StoredBatch {
+    state: BatchState,
+    expires_at: i64, // Expiry (Unix seconds)
+}
+
+/// Global store of batches keyed by batch id
+static BATCHES: Lazy<Mutex<HashMap<String, StoredBatch>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Generates a new random batch id
+pub fn new_batch_id() -> String {
+    let mut id_bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut id_bytes);
+    hex::encode(id_bytes)
+}
+
+/// Stores (or replaces) a batch under `batch_id` for `TLSN_VERIFIER_BATCH_TTL_SECS`,
+/// purging expired batches first
+pub fn store_batch(batch_id: &str, state: BatchState) {
+    store_batch_until(batch_id, state, chrono::Utc::now().timestamp() + config::get_batch_ttl_secs());
+}
+
+/// Stores (or replaces) a batch under `batch_id` until `expires_at` (Unix seconds),
+/// purging expired batches first
+fn store_batch_until(batch_id: &str, state: BatchState, expires_at: i64) {
+    let now = chrono::Utc::now().timestamp();
+    let mut batches = BATCHES.lock().unwrap();
+    batches.retain(|_, batch| batch.expires_at > now);
+    batches.insert(batch_id.to_string(), StoredBatch { state, expires_at });
+}
+
+/// Returns the state of a batch, or `None` if it does not exist or has expired
+pub fn get_batch(batch_id: &str) -> Option<BatchState> {
+    let now = chrono::Utc::now().timestamp();
+    BATCHES
+        .lock()
+        .unwrap()
+        .get(batch_id)
+        .filter(|batch| batch.expires_at > now)
+        .map(|batch| batch.state.clone())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Stores a batch that has already expired
+    pub(crate) fn store_expired_batch(batch_id: &str, state: BatchState) {
+        store_batch_until(batch_id, state, chrono::Utc::now().timestamp());
+    }
+}
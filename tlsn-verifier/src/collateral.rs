@@ -0,0 +1,149 @@
+use crate::attestation::read_attestation_report;
+use crate::config;
+use crate::http_client;
+use crate::quote;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Most recently fetched TDX collateral, swapped atomically by the refresh task
+static COLLATERAL: Lazy<RwLock<Option<Arc<CollateralState>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Cached TDX collateral (TCB info) and what it says about this platform
+pub struct CollateralState {
+    pub fetched_at: DateTime<Utc>,          // When the collateral was fetched
+    pub next_update: Option<DateTime<Utc>>, // `tcbInfo.nextUpdate`, after which it is stale
+    pub tcb_status: Option<String>,         // TCB status of this platform, if it could be matched
+}
+
+/// Collateral freshness summary exposed in `/health`
+#[derive(Serialize)]
+pub struct CollateralHealth {
+    pub fetched_at: Option<String>,  // RFC 3339 time of the last successful fetch
+    pub next_update: Option<String>, // RFC 3339 time after which the collateral is stale
+    pub stale: bool,                 // Whether the collateral is missing or past `next_update`
+    pub tcb_status: Option<String>,  // TCB status of this platform (e.g. `UpToDate`, `OutOfDate`)
+}
+
+/// Returns the current collateral, if any has been fetched
+pub fn current_collateral() -> Option<Arc<CollateralState>> {
+    COLLATERAL.read().unwrap().clone()
+}
+
+/// Summarizes collateral freshness for the health endpoint
+pub fn collateral_health() -> CollateralHealth {
+    let now = Utc::now();
+    match current_collateral() {
+        Some(state) => CollateralHealth {
+            fetched_at: Some(state.fetched_at.to_rfc3339()),
+            next_update: state.next_update.map(|t| t.to_rfc3339()),
+            stale: state.next_update.is_some_and(|t| t <= now),
+            tcb_status: state.tcb_status.clone(),
+        },
+        None => CollateralHealth { fetched_at: None, next_update: None, stale: true, tcb_status: None },
+    }
+}
+
+/// Starts the background task that periodically re-fetches collateral from
+/// `TLSN_VERIFIER_COLLATERAL_URL` and swaps it into the shared state.
+/// Does nothing if no collateral URL is configured. Must be called from within a Tokio runtime.
+pub fn start_collateral_refresh() {
+    let Some(url) = config::get_collateral_url() else {
+        info!("No collateral URL configured, refresh disabled");
+        return;
+    };
+    let interval = Duration::from_secs(config::get_collateral_refresh_secs());
+    tokio::spawn(async move {
+        loop {
+            match fetch_collateral(&url).await {
+                Ok(state) => swap_collateral(state),
+                Err(e) => warn!(error = %e, "Failed to refresh collateral"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    info!(interval_secs = interval.as_secs(), "Collateral refresh started");
+}
+
+/// Replaces the shared collateral, logging any change in this platform's TCB status
+fn swap_collateral(state: CollateralState) {
+    let previous_status = current_collateral().and_then(|s| s.tcb_status.clone());
+    if previous_status != state.tcb_status {
+        warn!(
+            previous = previous_status.as_deref().unwrap_or("<unknown>"),
+            current = state.tcb_status.as_deref().unwrap_or("<unknown>"),
+            "TCB status changed"
+        );
+    }
+    info!(next_update = ?state.next_update, "Collateral refreshed");
+    *COLLATERAL.write().unwrap() = Some(Arc::new(state));
+}
+
+/// Fetches the collateral document and matches this platform's TCB against it
+async fn fetch_collateral(url: &str) -> Result<CollateralState, String> {
+    let client = http_client::builder()?
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let raw: Value = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid collateral JSON: {}", e))?;
+
+    let tcb_info = raw.get("tcbInfo").ok_or("Collateral is missing 'tcbInfo'")?;
+    let next_update = tcb_info
+        .get("nextUpdate")
+        .and_then(Value::as_str)
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.with_timezone(&Utc));
+
+    // Matching requires this platform's TCB SVNs, taken from a fresh quote
+    let tcb_status = match own_tee_tcb_svn().await {
+        Ok(svn) => match_tcb_status(tcb_info, &svn),
+        Err(e) => {
+            warn!(error = %e, "Could not read own TCB SVN");
+            None
+        }
+    };
+
+    Ok(CollateralState { fetched_at: Utc::now(), next_update, tcb_status })
+}
+
+/// Reads this platform's tee_tcb_svn from a freshly generated quote
+async fn own_tee_tcb_svn() -> Result<[u8; quote::TEE_TCB_SVN_LEN], String> {
+    let (report, _) = read_attestation_report("", config::get_report_data_hash_algorithm())
+        .await
+        .map_err(|e| e.message)?;
+    quote::tee_tcb_svn_from_quote(&quote::decode_hex_quote(&report.quote)?)
+}
+
+/// Returns the status of the first TCB level whose TDX component SVNs are all
+/// less than or equal to the platform's (levels are ordered from newest to oldest)
+fn match_tcb_status(tcb_info: &Value, tee_tcb_svn: &[u8]) -> Option<String> {
+    tcb_info
+        .get("tcbLevels")?
+        .as_array()?
+        .iter()
+        .find(|level| {
+            level
+                .pointer("/tcb/tdxtcbcomponents")
+                .and_then(Value::as_array)
+                .is_some_and(|components| {
+                    components.iter().zip(tee_tcb_svn).all(|(component, svn)| {
+                        component.get("svn").and_then(Value::as_u64).is_some_and(|c| c <= *svn as u64)
+                    })
+                })
+        })
+        .and_then(|level| level.get("tcbStatus"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
@@ -0,0 +1,252 @@
+use crate::key_manager::try_get_key_material;
+use crate::types::{AttestationError, KeyMaterial, SchemeVerifyingKey, SignatureScheme, VerificationResult};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Base context of the W3C Verifiable Credentials Data Model 2.0
+const VC_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// Media type of a JSON-LD Verifiable Credential
+pub const VC_CONTENT_TYPE: &str = "application/vc";
+
+/// Multicodec prefix (varint of 0x1200) of a compressed P-256 public key in a `did:key`
+const P256_PUB_MULTICODEC: [u8; 2] = [0x80, 0x24];
+
+/// Multicodec prefix (varint of 0xed) of an Ed25519 public key in a `did:key`
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Media type of a DID document
+pub const DID_DOCUMENT_CONTENT_TYPE: &str = "application/did+ld+json";
+
+/// Multibase (base58btc, `z`-prefixed) encoding of the verifier's public key: multicodec
+/// p256-pub || compressed SEC1 key, or multicodec ed25519-pub || raw key
+fn public_key_multibase(key_material: &KeyMaterial) -> String {
+    let bytes = match key_material.verifying_key() {
+        SchemeVerifyingKey::P256(key) => [&P256_PUB_MULTICODEC[..], key.to_encoded_point(true).as_bytes()].concat(),
+        SchemeVerifyingKey::Ed25519(key) => [&ED25519_PUB_MULTICODEC[..], &key[..]].concat(),
+    };
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+/// Data Integrity cryptosuite of the credential proof for the key's signature scheme
+fn cryptosuite(key_material: &KeyMaterial) -> &'static str {
+    match key_material.scheme {
+        SignatureScheme::P256 => "ecdsa-jcs-2019",
+        SignatureScheme::Ed25519 => "eddsa-jcs-2022",
+    }
+}
+
+/// Derives the verifier's `did:key` from its public key
+pub fn issuer_did(key_material: &KeyMaterial) -> String {
+    format!("did:key:{}", public_key_multibase(key_material))
+}
+
+/// Returns the id of the `did:key`'s verification method (`did:key:z...#z...`)
+fn verification_method_id(key_material: &KeyMaterial) -> String {
+    format!("{}#{}", issuer_did(key_material), public_key_multibase(key_material))
+}
+
+/// Builds the DID document of the verifier's `did:key`, listing its public key as a `Multikey`
+/// verification method, so VC consumers can resolve the issuer's key
+pub fn did_document(key_material: &KeyMaterial) -> Value {
+    let did = issuer_did(key_material);
+    let verification_method = verification_method_id(key_material);
+    json!({
+        "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+        "id": did,
+        "verificationMethod": [{
+            "id": verification_method,
+            "type": "Multikey",
+            "controller": did,
+            "publicKeyMultibase": public_key_multibase(key_material),
+        }],
+        "authentication": [verification_method],
+        "assertionMethod": [verification_method],
+    })
+}
+
+/// Serializes a JSON value per the JSON Canonicalization Scheme (RFC 8785): object members
+/// sorted by their UTF-16 code units, numbers in ECMAScript form, no insignificant whitespace.
+/// Strings are escaped as serde_json does, which matches the scheme: only `"`, `\` and control
+/// characters, the latter as `\b`, `\t`, `\n`, `\f`, `\r` or lowercase `\u00xx`.
+fn canonicalize(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut members: Vec<(&String, &Value)> = map.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            let members: Vec<String> = members
+                .into_iter()
+                .map(|(key, value)| format!("{}:{}", Value::String(key.clone()), canonicalize(value)))
+                .collect();
+            format!("{{{}}}", members.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonicalize).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::Number(n) => n.as_f64().map_or_else(|| n.to_string(), canonical_number),
+        other => other.to_string(),
+    }
+}
+
+/// Formats a number as ECMAScript's `Number.prototype.toString` does (RFC 8785 section 3.2.2.3):
+/// the shortest digits that round-trip, in plain notation for magnitudes in `1e-6..1e21`
+/// and as `d.ddde+x` / `d.ddde-x` otherwise
+fn canonical_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string(); // Also for -0
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    // `{:e}` gives the shortest round-trip digit count, e.g. `1.2345e-7`. Formatting again with that
+    // precision picks the closest digits, with ties to even as ECMAScript does (1424953923781206.25
+    // has two closest shortest forms and becomes `1424953923781206.2`).
+    let shortest = format!("{:e}", value.abs());
+    let precision = shortest.split_once('e').map_or(0, |(mantissa, _)| mantissa.len().saturating_sub(2));
+    let formatted = format!("{:.*e}", precision, value.abs());
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1; // Position of the decimal point
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{}e{}{}", &digits[..1], fraction, if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    };
+    format!("{}{}", sign, body)
+}
+
+/// Issues a W3C Verifiable Credential whose subject is the verified score, issued by the
+/// verifier's `did:key` and secured with an `ecdsa-jcs-2019` (P-256) or `eddsa-jcs-2022` (Ed25519)
+/// Data Integrity proof: the key signs `SHA-256(JCS(proof config)) || SHA-256(JCS(credential))`.
+/// The key of `TLSN_VERIFIER_SIG_SCHEME` is used regardless of `TLSN_VERIFIER_SIGNING_MODE`.
+pub fn issue_credential(result: &VerificationResult) -> Result<Value, AttestationError> {
+    let key_material = try_get_key_material().ok_or_else(|| AttestationError {
+        code: None,
+        message: "Key material not initialized".to_string(),
+    })?;
+    let issuer = issuer_did(&key_material);
+    let verification_method = verification_method_id(&key_material);
+    let issued_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut credential = json!({
+        "@context": [VC_CONTEXT],
+        "type": ["VerifiableCredential", "TlsnCreditScoreCredential"],
+        "issuer": issuer,
+        "validFrom": issued_at,
+        "credentialSubject": {
+            "type": "TlsnVerifiedScore",
+            "score": result.score,
+            "serverName": result.server_name,
+            "sessionTime": result.time,
+            "notaryKey": result.verifying_key,
+            "resultId": result.result_id,
+        },
+    });
+
+    let mut proof = json!({
+        "@context": [VC_CONTEXT],
+        "type": "DataIntegrityProof",
+        "cryptosuite": cryptosuite(&key_material),
+        "created": issued_at,
+        "verificationMethod": verification_method,
+        "proofPurpose": "assertionMethod",
+    });
+    let mut hash_data = Sha256::digest(canonicalize(&proof).as_bytes()).to_vec();
+    hash_data.extend_from_slice(&Sha256::digest(canonicalize(&credential).as_bytes()));
+    let signature = key_material.sign_message(&hash_data);
+    proof["proofValue"] = Value::String(format!("z{}", bs58::encode(signature).into_string()));
+
+    credential["proof"] = proof;
+    Ok(credential)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_formatted_like_ecmascript() {
+        // IEEE 754 bit patterns and their canonical form, from RFC 8785 appendix B
+        let cases: [(u64, &str); 25] = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x444b1ae4d6e2ef51, "1.0000000000000001e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in cases {
+            assert_eq!(canonical_number(f64::from_bits(bits)), expected, "{:#018x}", bits);
+        }
+    }
+
+    #[test]
+    fn members_are_sorted_by_utf16_code_units() {
+        // RFC 8785 section 3.2.3: the emoji (a surrogate pair) sorts before U+FB33
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis",
+        });
+        let expected = concat!(
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+            "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+            "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+        assert_eq!(canonicalize(&value), expected);
+    }
+
+    #[test]
+    #[allow(clippy::excessive_precision)] // The sample's number literals, as written in the RFC
+    fn rfc_8785_sample_is_canonicalized() {
+        // RFC 8785 section 3.2.2: number formatting and string escaping. The numbers are Rust
+        // literals since serde_json's default float parser may be off by one unit in the last place.
+        let mut value: Value = serde_json::from_str(
+            r#"{
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        value["numbers"] = json!([333333333.33333329_f64, 1E30, 4.50, 2e-3, 0.000000000000000000000000001]);
+        assert_eq!(
+            canonicalize(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn integers_and_nesting_are_canonicalized() {
+        let value = json!({"b": [1, -2, 10000000000000000000u64], "a": {"d": 1.0, "c": "x"}});
+        assert_eq!(canonicalize(&value), r#"{"a":{"c":"x","d":1},"b":[1,-2,10000000000000000000]}"#);
+    }
+}
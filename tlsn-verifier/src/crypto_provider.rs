@@ -0,0 +1,125 @@
+use crate::config;
+use once_cell::sync::{Lazy, OnceCell};
+use std::sync::Mutex;
+use std::time::Instant;
+use tlsn_core::hash::{Blake3, HashAlgId, HashAlgorithm, Keccak256, Sha256};
+use tlsn_core::signing::{KeyAlgId, SignatureVerifier};
+use tlsn_core::CryptoProvider;
+use tracing::info;
+
+/// Singleton CryptoProvider shared by all verifications
+static CRYPTO_PROVIDER: OnceCell<CryptoProvider> = OnceCell::new();
+
+/// Algorithms registered on top of the defaults, applied when the shared provider is built
+static REGISTRATIONS: Lazy<Mutex<Vec<Registration>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Ids of all registered algorithms, kept after the registrations are consumed
+static REGISTERED_IDS: Lazy<Mutex<SupportedAlgorithms>> =
+    Lazy::new(|| Mutex::new(SupportedAlgorithms { hash: Vec::new(), signature: Vec::new() }));
+
+/// Hash and signature algorithm ids available in `CryptoProvider::default()`
+const DEFAULT_HASH_ALGORITHMS: [HashAlgId; 3] = [HashAlgId::SHA256, HashAlgId::BLAKE3, HashAlgId::KECCAK256];
+const DEFAULT_SIGNATURE_ALGORITHMS: [KeyAlgId; 2] = [KeyAlgId::K256, KeyAlgId::P256];
+
+/// An algorithm to add to the shared provider
+enum Registration {
+    Hash(HashAlgId, Box<dyn HashAlgorithm + Send + Sync>),
+    Signature(KeyAlgId, Box<dyn SignatureVerifier + Send + Sync>),
+}
+
+/// Algorithm ids the shared provider supports, for error messages
+pub struct SupportedAlgorithms {
+    pub hash: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Registers an additional hash algorithm under `id` for the shared provider.
+/// Fails if the provider has already been built.
+pub fn register_hash_algorithm(
+    id: HashAlgId,
+    algorithm: Box<dyn HashAlgorithm + Send + Sync>,
+) -> Result<(), String> {
+    register(Registration::Hash(id, algorithm))
+}
+
+/// Registers an additional signature verifier for the shared provider.
+/// Fails if the provider has already been built. Not used by the binary itself;
+/// deployments with their own signature suites call it before startup completes.
+#[allow(dead_code)]
+pub fn register_signature_verifier(verifier: Box<dyn SignatureVerifier + Send + Sync>) -> Result<(), String> {
+    register(Registration::Signature(verifier.alg_id(), verifier))
+}
+
+fn register(registration: Registration) -> Result<(), String> {
+    if CRYPTO_PROVIDER.get().is_some() {
+        return Err("CryptoProvider already built; register algorithms before the first verification".to_string());
+    }
+    let mut ids = REGISTERED_IDS.lock().unwrap();
+    match &registration {
+        Registration::Hash(id, _) => ids.hash.push(id.as_u8()),
+        Registration::Signature(id, _) => ids.signature.push(id.as_u8()),
+    }
+    REGISTRATIONS.lock().unwrap().push(registration);
+    Ok(())
+}
+
+/// Registers the hash algorithm aliases from `TLSN_VERIFIER_HASH_ALGORITHM_ALIASES`,
+/// so notaries using custom algorithm ids for a built-in hash can be verified.
+/// Fails on unknown algorithm names.
+pub fn register_configured_algorithms() -> Result<(), String> {
+    for (id, name) in config::get_hash_algorithm_aliases() {
+        let algorithm: Box<dyn HashAlgorithm + Send + Sync> = match name.as_str() {
+            "sha256" => Box::new(Sha256::default()),
+            "blake3" => Box::new(Blake3::default()),
+            "keccak256" => Box::new(Keccak256::default()),
+            other => {
+                return Err(format!(
+                    "Unknown hash algorithm '{}' for id {}; expected sha256, blake3 or keccak256",
+                    other, id
+                ));
+            }
+        };
+        register_hash_algorithm(HashAlgId::new(id), algorithm)?;
+        info!(id, name = %name, "Registered hash algorithm");
+    }
+    Ok(())
+}
+
+/// Builds a CryptoProvider from the defaults plus all registered algorithms
+fn build_crypto_provider() -> CryptoProvider {
+    let mut provider = CryptoProvider::default();
+    for registration in REGISTRATIONS.lock().unwrap().drain(..) {
+        match registration {
+            Registration::Hash(id, algorithm) => provider.hash.set_algorithm(id, algorithm),
+            Registration::Signature(_, verifier) => provider.signature.set_verifier(verifier),
+        }
+    }
+    provider
+}
+
+/// Returns the hash and signature algorithm ids of the shared provider
+pub fn supported_algorithms() -> SupportedAlgorithms {
+    let mut hash: Vec<u8> = DEFAULT_HASH_ALGORITHMS.iter().map(HashAlgId::as_u8).collect();
+    let mut signature: Vec<u8> = DEFAULT_SIGNATURE_ALGORITHMS.iter().map(KeyAlgId::as_u8).collect();
+    let registered = REGISTERED_IDS.lock().unwrap();
+    hash.extend(&registered.hash);
+    signature.extend(&registered.signature);
+    hash.sort_unstable();
+    hash.dedup();
+    signature.sort_unstable();
+    signature.dedup();
+    SupportedAlgorithms { hash, signature }
+}
+
+/// Returns the shared CryptoProvider, constructing it on first use
+pub fn get_crypto_provider() -> &'static CryptoProvider {
+    CRYPTO_PROVIDER.get_or_init(build_crypto_provider)
+}
+
+/// Constructs the shared CryptoProvider at startup instead of on the first request,
+/// logging how long construction took
+pub fn warmup_crypto_provider() {
+    let start = Instant::now();
+    get_crypto_provider();
+    info!(elapsed_ms = start.elapsed().as_secs_f64() * 1000.0, "CryptoProvider warmed up");
+}
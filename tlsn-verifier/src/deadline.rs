@@ -0,0 +1,45 @@
+use actix_web::HttpRequest;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Header carrying the client's overall latency budget in milliseconds
+pub const DEADLINE_HEADER: &str = "x-deadline-ms";
+
+/// Optional overall deadline for a request, shared by verification and attestation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// Builds the deadline from the `X-Deadline-Ms` header, measured from now.
+    /// Missing or malformed headers yield no deadline.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let budget_ms = req
+            .headers()
+            .get(DEADLINE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        match budget_ms {
+            Some(ms) => Deadline(Some(Instant::now() + Duration::from_millis(ms))),
+            None => Deadline(None),
+        }
+    }
+
+    /// Returns the time left before the deadline, or `None` if there is no deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0.map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns whether the deadline has already passed
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_some_and(|left| left.is_zero())
+    }
+
+    /// Runs `future` to completion, or returns `None` if the deadline passes first
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        match self.0 {
+            Some(at) => tokio::time::timeout_at(at, future).await.ok(),
+            None => Some(future.await),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::config;
+use crate::types::{AttestationError, VerificationError};
+use tracing::warn;
+
+/// Client-safe messages for error codes, used when verbose errors are off.
+/// They describe what was wrong without internal detail (regexes, parser output, paths).
+const PUBLIC_MESSAGES: &[(&str, &str)] = &[
+    ("EMPTY_PRESENTATION", "The presentation is empty"),
+    ("INVALID_HEX", "The presentation data is not valid hex"),
+    ("TRUNCATED_PRESENTATION", "The presentation data is truncated"),
+    ("TRAILING_BYTES", "The presentation data has unexpected trailing bytes"),
+    ("PRESENTATION_VERSION_MISMATCH", "The presentation was created with an unsupported TLSN version"),
+    ("INVALID_PRESENTATION_ENCODING", "The presentation could not be decoded"),
+    ("CERT_FINGERPRINT_MISMATCH", "The server certificate does not match the pinned fingerprint"),
+    ("SNI_CERT_MISMATCH", "The server certificate does not match the server name"),
+    ("INVALID_ENCODED_PAYLOAD", "The encoded score payload could not be decoded"),
+    ("UNSUPPORTED_CHARSET", "The response charset is not accepted"),
+    ("REPORT_DATA_MISMATCH", "The attestation quote does not match the requested data"),
+    ("VERSION_NOT_ACCEPTED", "The presentation version is not accepted for this server"),
+    ("UNSUPPORTED_ALGORITHM", "The presentation uses an unsupported algorithm"),
+    ("WEAK_NOTARY_KEY", "The notary key does not meet the key strength policy"),
+    ("NOTARY_ATTESTATION_REQUIRED", "A notary attestation is required"),
+    ("INVALID_NOTARY_ATTESTATION", "The notary attestation is invalid"),
+    ("NOTARY_KEY_NOT_ATTESTED", "The notary key is not covered by the notary attestation"),
+    ("PROOF_OUTSIDE_WINDOW", "The proof was captured outside the accepted time window"),
+    ("INSUFFICIENT_AUTHENTICATION", "Too little of the transcript is authenticated"),
+    ("TOO_MANY_REDACTIONS", "The transcript is split into too many redacted regions"),
+    ("UNEXPECTED_RESPONSE_STATUS", "The proven response did not succeed"),
+    ("AMBIGUOUS_HOST", "The request contains conflicting Host headers"),
+    ("HOST_PORT_MISMATCH", "The request was sent to an unexpected port"),
+    ("MISSING_AUTHZ", "The request lacks the required Authorization header"),
+    ("MISSING_REQUEST_HEADER", "The request lacks a required header"),
+    ("UNEXPECTED_EXTRA_REQUESTS", "The transcript contains unexpected additional requests"),
+    ("SCORE_NOT_FOUND", "The response does not contain a score"),
+    ("INVALID_SCORE_PATH", "The configured score path is invalid"),
+    ("CALLBACK_QUEUE_FULL", "Too many callbacks are pending; retry later"),
+    ("AMBIGUOUS_SCORE", "The response contains more than one score"),
+    ("SCORE_BELOW_MINIMUM", "The score is below the accepted minimum"),
+    ("SCORE_BELOW_REQUIRED", "The score is below the minimum required for this request"),
+    ("SCORE_NOT_NUMERIC", "The score is not a number"),
+    ("TRUNCATED_RESPONSE", "The response in the transcript is truncated"),
+    ("SCHEMA_VALIDATION_FAILED", "The response body does not match the expected schema"),
+    ("BODY_TIMESTAMP_MISSING", "The response lacks a valid timestamp"),
+    ("BODY_STALE", "The response data is too old"),
+    ("BODY_TIMESTAMP_IN_FUTURE", "The response timestamp lies in the future"),
+    ("DEADLINE_EXCEEDED", "The request deadline was exceeded"),
+    ("INVALID_SESSION_REF", "The session reference is invalid"),
+    ("UNKNOWN_SESSION", "The session does not exist or has expired"),
+    ("INVALID_BASE_SESSION", "The base presentation is invalid"),
+    ("SESSION_STORE_FULL", "The session store is full, try again later"),
+    ("INVALID_CORRELATION", "Correlation needs at least two presentations, within the batch size limit"),
+    ("SUBJECT_MISSING", "A response does not contain the subject field"),
+    ("STALE_RELATIVE_TO_PREVIOUS", "A newer proof was already verified for this subject"),
+    ("SUBJECT_STORE_FULL", "Too many subjects are tracked, try again later"),
+    ("SUBJECT_MISMATCH", "The presentations are not about the same subject"),
+    ("INVALID_BATCH", "The batch must be a non-empty JSON array of presentations"),
+    ("BATCH_TOO_LARGE", "The batch has more presentations than allowed"),
+    ("UNKNOWN_BATCH", "The batch does not exist or has expired"),
+    ("NOT_MERKLE_BATCH", "The batch was not attested with a Merkle root"),
+    ("UNKNOWN_BATCH_ITEM", "The batch has no item at this index"),
+    ("INVALID_DIGEST", "digest_hex must be a 32- or 64-byte hex digest"),
+    ("QUOTE_QUEUE_FULL", "The attestation provider is busy, try again later"),
+    ("QUOTE_QUEUE_TIMEOUT", "The attestation provider timed out, try again later"),
+    ("QUOTE_WORKER_UNAVAILABLE", "The attestation provider is unavailable, try again later"),
+    ("MEASUREMENT_NOT_ALLOWED", "The verifier's measurements are not allowed by policy"),
+];
+
+/// Returns the client-safe message for an error code, or `fallback` for unknown or missing codes
+fn public_message(code: &Option<String>, fallback: &str) -> String {
+    code.as_deref()
+        .and_then(|code| PUBLIC_MESSAGES.iter().find(|(c, _)| *c == code))
+        .map_or(fallback, |(_, message)| message)
+        .to_string()
+}
+
+/// Logs the detailed verification error server-side and, unless `TLSN_VERIFIER_VERBOSE_ERRORS`
+/// is on, replaces its message with a client-safe one (the code is kept)
+pub fn public_verification_error(error: VerificationError) -> VerificationError {
+    warn!(code = ?error.code, message = %error.message, "Verification failed");
+    if config::get_verbose_errors() {
+        return error;
+    }
+    let message = public_message(&error.code, "Proof verification failed");
+    VerificationError { code: error.code, message }
+}
+
+/// Logs the detailed attestation error server-side and, unless `TLSN_VERIFIER_VERBOSE_ERRORS`
+/// is on, replaces its message with a client-safe one (the code is kept)
+pub fn public_attestation_error(error: AttestationError) -> AttestationError {
+    warn!(code = ?error.code, message = %error.message, "Attestation failed");
+    if config::get_verbose_errors() {
+        return error;
+    }
+    let message = public_message(&error.code, "Attestation failed");
+    AttestationError { code: error.code, message }
+}
@@ -0,0 +1,49 @@
+use hex::FromHexError;
+
+/// Decodes a hex string into bytes, skipping whitespace (e.g. line breaks in pasted presentations).
+/// Character indices in errors count non-whitespace characters only.
+pub fn decode_hex_skipping_whitespace(data: &str) -> Result<Vec<u8>, FromHexError> {
+    let mut out = Vec::with_capacity(data.len() / 2);
+    let mut high_nibble: Option<u8> = None;
+    for (index, c) in data.chars().filter(|c| !c.is_whitespace()).enumerate() {
+        let nibble = c.to_digit(16).ok_or(FromHexError::InvalidHexCharacter { c, index })? as u8;
+        match high_nibble.take() {
+            Some(high) => out.push((high << 4) | nibble),
+            None => high_nibble = Some(nibble),
+        }
+    }
+    if high_nibble.is_some() {
+        return Err(FromHexError::OddLength);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_hex_skipping_whitespace_matches_hex_decode() {
+        let cases = ["", "00", "deadBEEF", "de ad\nbe\tef", " 0a0b \r\n0c "];
+        for data in cases {
+            let compact: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            assert_eq!(decode_hex_skipping_whitespace(data), hex::decode(&compact), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn decode_hex_skipping_whitespace_errors() {
+        assert_eq!(decode_hex_skipping_whitespace("abc"), Err(FromHexError::OddLength));
+        assert_eq!(decode_hex_skipping_whitespace("a b c"), Err(FromHexError::OddLength));
+        // Indices count non-whitespace characters only
+        assert_eq!(
+            decode_hex_skipping_whitespace("ab \n cz"),
+            Err(FromHexError::InvalidHexCharacter { c: 'z', index: 3 })
+        );
+        assert_eq!(
+            decode_hex_skipping_whitespace("0x12"),
+            Err(FromHexError::InvalidHexCharacter { c: 'x', index: 1 })
+        );
+        assert!(decode_hex_skipping_whitespace("１２").is_err());
+    }
+}
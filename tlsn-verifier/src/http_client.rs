@@ -0,0 +1,69 @@
+use crate::config;
+use reqwest::{ClientBuilder, Proxy};
+
+/// Returns a client builder for outbound HTTP requests, routed through the configured proxy
+/// (`TLSN_VERIFIER_PROXY_URL` or `HTTPS_PROXY`) if any.
+/// Fails if the proxy URL is invalid.
+pub fn builder() -> Result<ClientBuilder, String> {
+    builder_with_proxy(config::get_proxy_url().as_deref())
+}
+
+/// Returns a client builder routed through `proxy_url`, or connecting directly if `None`
+fn builder_with_proxy(proxy_url: Option<&str>) -> Result<ClientBuilder, String> {
+    let builder = reqwest::Client::builder();
+    match proxy_url {
+        Some(url) => {
+            let proxy = Proxy::all(url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+            Ok(builder.proxy(proxy))
+        }
+        None => Ok(builder),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Sends one GET for `url` through a proxy listening locally (with `credentials` embedded in
+    /// its URL) and returns the request head the proxy received
+    async fn request_seen_by_proxy(url: &str, credentials: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}{}", credentials, listener.local_addr().unwrap());
+        let proxy = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                head.extend_from_slice(&buf[..n]);
+            }
+            socket.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+            String::from_utf8(head).unwrap()
+        });
+        let client = builder_with_proxy(Some(&proxy_url)).unwrap().build().unwrap();
+        client.get(url).send().await.unwrap();
+        proxy.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_go_through_the_proxy() {
+        let head = request_seen_by_proxy("http://collateral.invalid/tcb", "").await;
+        assert!(head.starts_with("GET http://collateral.invalid/tcb HTTP/1.1\r\n"), "{}", head);
+        assert!(!head.to_lowercase().contains("proxy-authorization"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn proxy_credentials_are_sent_as_basic_auth() {
+        let head = request_seen_by_proxy("http://collateral.invalid/tcb", "user:pass@").await;
+        // base64("user:pass")
+        assert!(head.to_lowercase().contains("proxy-authorization: basic dxnlcjpwyxnz\r\n"), "{}", head);
+    }
+
+    #[test]
+    fn invalid_proxy_url_is_rejected() {
+        assert!(builder_with_proxy(Some("not a url")).is_err());
+        assert!(builder_with_proxy(None).is_ok());
+    }
+}
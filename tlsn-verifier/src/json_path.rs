@@ -0,0 +1,133 @@
+use serde_json::Value;
+
+/// One step of a parsed JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),                                  // `.name` or `['name']`
+    Index(usize),                                 // `[0]`
+    Wildcard,                                     // `[*]` or `.*`
+    Filter { field: Vec<String>, value: Value },  // `[?(@.field == literal)]`
+}
+
+/// Parses the supported JSONPath subset: `$` followed by `.name`, `['name']`, `[N]`, `[*]`, `.*`
+/// and equality filters over array elements, e.g. `$.scores[?(@.type=="fico")].value`
+fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| format!("JSONPath '{}' must start with '$'", path))?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix(".*") {
+            segments.push(Segment::Wildcard);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(after.len());
+            if end == 0 {
+                return Err(format!("Empty member name in JSONPath '{}'", path));
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix("[?(") {
+            let end = after
+                .find(")]")
+                .ok_or_else(|| format!("Unterminated filter in JSONPath '{}'", path))?;
+            segments.push(parse_filter(&after[..end], path)?);
+            rest = &after[end + 2..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .ok_or_else(|| format!("Unterminated '[' in JSONPath '{}'", path))?;
+            let inner = after[..end].trim();
+            let segment = if inner == "*" {
+                Segment::Wildcard
+            } else if let Ok(index) = inner.parse() {
+                Segment::Index(index)
+            } else {
+                Segment::Key(unquote(inner).ok_or_else(|| format!("Invalid selector '[{}]' in JSONPath '{}'", inner, path))?)
+            };
+            segments.push(segment);
+            rest = &after[end + 1..];
+        } else {
+            return Err(format!("Unexpected '{}' in JSONPath '{}'", rest, path));
+        }
+    }
+    Ok(segments)
+}
+
+/// Parses a filter body of the form `@.field.sub == literal`
+fn parse_filter(filter: &str, path: &str) -> Result<Segment, String> {
+    let (lhs, rhs) = filter
+        .split_once("==")
+        .ok_or_else(|| format!("Only '==' filters are supported in JSONPath '{}'", path))?;
+    let field: Vec<String> = lhs
+        .trim()
+        .strip_prefix("@.")
+        .ok_or_else(|| format!("Filter must compare '@.<field>' in JSONPath '{}'", path))?
+        .split('.')
+        .map(str::to_string)
+        .collect();
+    if field.iter().any(String::is_empty) {
+        return Err(format!("Empty filter field in JSONPath '{}'", path));
+    }
+    let rhs = rhs.trim();
+    let value = match unquote(rhs) {
+        Some(s) => Value::String(s),
+        None => serde_json::from_str(rhs)
+            .map_err(|_| format!("Invalid filter literal '{}' in JSONPath '{}'", rhs, path))?,
+    };
+    Ok(Segment::Filter { field, value })
+}
+
+/// Strips matching single or double quotes
+fn unquote(s: &str) -> Option<String> {
+    ['\'', '"'].iter().find_map(|quote| {
+        s.strip_prefix(*quote)
+            .and_then(|s| s.strip_suffix(*quote))
+            .map(str::to_string)
+    })
+}
+
+/// Checks that `path` is a valid expression of the supported JSONPath subset
+pub fn validate(path: &str) -> Result<(), String> {
+    parse(path).map(|_| ())
+}
+
+/// Returns the member name selected by the last segment of `path`, if that segment is a key
+pub fn last_key(path: &str) -> Option<String> {
+    match parse(path).ok()?.pop()? {
+        Segment::Key(key) => Some(key),
+        _ => None,
+    }
+}
+
+/// Returns every value in `root` selected by the JSONPath expression `path`
+pub fn select<'a>(root: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let mut current = vec![root];
+    for segment in parse(path)? {
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&'a Value> {
+                match (&segment, value) {
+                    (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Segment::Index(index), Value::Array(items)) => items.get(*index).into_iter().collect(),
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    (Segment::Filter { field, value: expected }, Value::Array(items)) => items
+                        .iter()
+                        .filter(|item| {
+                            field
+                                .iter()
+                                .try_fold(*item, |v, key| v.get(key))
+                                .is_some_and(|actual| actual == expected)
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    Ok(current)
+}
@@ -1,73 +1,330 @@
 use crate::types::*;
 use crate::types::KeyManagerError;
 use once_cell::sync::OnceCell;
+use crate::config;
 use crate::tappd_service;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
 /// Holds a private ECDSA signing key, its origin, and optional certificate chain
 
-/// Singleton that stores the initialized KeyMaterial
-static KEY_MATERIAL: OnceCell<KeyMaterial> = OnceCell::new();
+/// Singleton that stores the current KeyMaterial; `POST /rotate-key` swaps it
+static KEY_MATERIAL: RwLock<Option<Arc<KeyMaterial>>> = RwLock::new(None);
+
+/// Serializes rotations, so concurrent ones do not interleave their key file writes
+static ROTATION_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Singleton that stores the operator's offline key, if one is configured
+static OPERATOR_KEY: OnceCell<KeyMaterial> = OnceCell::new();
+
+/// PBKDF2-HMAC-SHA256 iterations deriving the key file's encryption key from the passphrase
+const KEY_FILE_PBKDF2_ITERATIONS: u32 = 200_000;
+
+/// Length of the random PBKDF2 salt stored in the key file
+const KEY_FILE_SALT_LEN: usize = 16;
+
+/// On-disk format of `TLSN_VERIFIER_KEY_FILE`: the signing key's secret scalar,
+/// encrypted with AES-256-GCM under a key derived from the passphrase
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeyFile {
+    version: u32,
+    salt_hex_encoded: String,
+    nonce_hex_encoded: String,
+    ciphertext_hex_encoded: String,
+}
 
 
 async fn derive_key_from_tappd() -> Result<GetKeyResponse, KeyManagerError> {
-    println!("[derive_key_from_tappd] Requesting key material from Tappd service");
+    debug!("Requesting key material from tappd");
     let res = tappd_service::send_key_request().await.map_err(|e| {
         KeyManagerError {
             message: format!("Tappd Service Error: {}", e.message),
         }
     })?;
-    println!("[derive_key_from_tappd] Response received from Tappd service");
-    let body_bytes = hyper::body::to_bytes(res.into_body())
-        .await
-        .map_err(|e| KeyManagerError {
-            message: format!("Failed to read response body: {}", e),
-        })?;
-    println!("[derive_key_from_tappd] Response body read successfully");
     let parsed: GetKeyResponse =
-        serde_json::from_slice(&body_bytes).map_err(|e| KeyManagerError {
+        serde_json::from_slice(res.body()).map_err(|e| KeyManagerError {
             message: format!("Failed to parse GetKeyResponse: {}", e),
         })?;
-    println!("[derive_key_from_tappd] GetKeyResponse parsed successfully");
+    debug!("GetKeyResponse parsed");
     Ok(parsed)
 }
 
 
-pub async fn init_key_material_from_tappd_socket() -> Result<(), KeyManagerError> {
+fn key_file_error(path: &str, message: impl std::fmt::Display) -> KeyManagerError {
+    KeyManagerError {
+        message: format!("Key file {}: {}", path, message),
+    }
+}
+
+/// Derives the AES-256-GCM key protecting the key file from the passphrase and salt
+fn key_file_cipher(passphrase: &str, salt: &[u8]) -> LessSafeKey {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(KEY_FILE_PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).expect("AES-256 key is 32 bytes"))
+}
+
+/// Reads and decrypts a persisted random key
+fn load_key_file(path: &str, passphrase: &str) -> Result<KeyMaterial, KeyManagerError> {
+    let contents = fs::read_to_string(path).map_err(|e| key_file_error(path, format!("cannot read: {}", e)))?;
+    let file: EncryptedKeyFile =
+        serde_json::from_str(&contents).map_err(|e| key_file_error(path, format!("invalid format: {}", e)))?;
+    if file.version != 1 {
+        return Err(key_file_error(path, format!("unsupported version {}", file.version)));
+    }
+    let decode = |field: &str, value: &str| {
+        hex::decode(value).map_err(|e| key_file_error(path, format!("invalid {}: {}", field, e)))
+    };
+    let salt = decode("salt", &file.salt_hex_encoded)?;
+    let nonce = Nonce::try_assume_unique_for_key(&decode("nonce", &file.nonce_hex_encoded)?)
+        .map_err(|_| key_file_error(path, "invalid nonce length"))?;
+    let mut in_out = decode("ciphertext", &file.ciphertext_hex_encoded)?;
+    let secret = key_file_cipher(passphrase, &salt)
+        .open_in_place(nonce, Aad::empty(), &mut in_out)
+        .map_err(|_| key_file_error(path, "cannot decrypt (wrong passphrase or corrupted file)"))?;
+    KeyMaterial::from_random_key_bytes(secret).map_err(|e| key_file_error(path, format!("invalid key: {}", e)))
+}
+
+/// Encrypts the random key and writes it to `path`, readable by the owner only.
+/// Writes a temporary file first and renames it, so an existing key file is replaced atomically.
+fn save_key_file(path: &str, passphrase: &str, key_material: &KeyMaterial) -> Result<(), KeyManagerError> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; KEY_FILE_SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| key_file_error(path, "cannot generate randomness"))?;
+    let mut in_out = key_material.signing_key.to_bytes().to_vec();
+    key_file_cipher(passphrase, &salt)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut in_out)
+        .map_err(|_| key_file_error(path, "cannot encrypt"))?;
+    let file = EncryptedKeyFile {
+        version: 1,
+        salt_hex_encoded: hex::encode(salt),
+        nonce_hex_encoded: hex::encode(nonce),
+        ciphertext_hex_encoded: hex::encode(in_out),
+    };
+    let contents = serde_json::to_string(&file).map_err(|e| key_file_error(path, e))?;
+
+    let tmp_path = format!("{}.tmp", path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut out = options
+        .open(&tmp_path)
+        .map_err(|e| key_file_error(path, format!("cannot create: {}", e)))?;
+    std::io::Write::write_all(&mut out, contents.as_bytes())
+        .and_then(|_| out.sync_all())
+        .map_err(|e| key_file_error(path, format!("cannot write: {}", e)))?;
+    fs::rename(&tmp_path, path).map_err(|e| key_file_error(path, format!("cannot replace: {}", e)))
+}
+
+/// Replaces a freshly generated random key with the one persisted in `TLSN_VERIFIER_KEY_FILE`,
+/// or persists it there if the file does not exist yet. With `replace` (on rotation) the
+/// generated key always overwrites the file. Without a key file the key is kept as is.
+fn persisted_random_key(generated: KeyMaterial, replace: bool) -> Result<KeyMaterial, KeyManagerError> {
+    let Some(path) = config::get_key_file() else {
+        return Ok(generated);
+    };
+    let passphrase = config::get_key_file_passphrase().ok_or_else(|| KeyManagerError {
+        message: "TLSN_VERIFIER_KEY_FILE is set but TLSN_VERIFIER_KEY_FILE_PASSPHRASE is not".to_string(),
+    })?;
+    reload_or_persist_key(&path, &passphrase, generated, replace)
+}
+
+/// Loads the key persisted at `path`, or persists `generated` there if the file does not exist
+/// yet or `replace` is set
+fn reload_or_persist_key(
+    path: &str,
+    passphrase: &str,
+    generated: KeyMaterial,
+    replace: bool,
+) -> Result<KeyMaterial, KeyManagerError> {
+    if !replace && Path::new(path).exists() {
+        let key_material = load_key_file(path, passphrase)?;
+        info!(path = %path, verifying_key = %key_material.encode_verify_key(), "Reusing random key from key file");
+        return Ok(key_material);
+    }
+    save_key_file(path, passphrase, &generated)?;
+    info!(path = %path, verifying_key = %generated.encode_verify_key(), "Persisted new random key to key file");
+    Ok(generated)
+}
+
+/// Derives key material from tappd, falling back to a random key, and selects the signature
+/// scheme of `TLSN_VERIFIER_SIG_SCHEME` (the master key and key file stay P-256).
+/// A random key is persisted to (or, unless `rotating`, reloaded from) `TLSN_VERIFIER_KEY_FILE`
+/// if configured; a tappd-derived key is never written to disk.
+async fn derive_key_material(rotating: bool) -> Result<KeyMaterial, KeyManagerError> {
     let key_material = match derive_key_from_tappd().await {
         Ok(key_response) => {
             // Try to parse key and certificate from response
-            println!("Successfully derived key from Tappd");
             match KeyMaterial::from_get_key_response(&key_response) {
                 Ok(km) => {
-                    println!("Successfully created signing key from Tappd key");
+                    info!("Derived signing key from tappd");
                     km
                 }
                 Err(e) => {
-                    println!("Error creating signing key from Tappd key: {}", e);
-                    println!("Falling back to random key generation");
+                    warn!(error = %e, "Invalid tappd key, falling back to a random key");
                     KeyMaterial::new_random()
                 }
             }
         }
         Err(e) => {
             // If Tappd fails, generate a local key instead
-            println!("Error deriving key from Tappd: {:?}", e);
-            println!("Falling back to random key generation");
+            warn!(error = %e.message, "Deriving key from tappd failed, falling back to a random key");
             KeyMaterial::new_random()
         }
     };
+    let key_material = match key_material.source {
+        KeySource::Random => persisted_random_key(key_material, rotating)?,
+        _ => key_material,
+    };
+    Ok(key_material.with_scheme(config::get_signature_scheme()))
+}
+
+/// Derives the key material (see `derive_key_material`) and stores it globally.
+/// Idempotent: if the key material is already initialized (e.g. init runs again on reload),
+/// the existing key is kept and a warning is logged; only `rotate_key_material` replaces it.
+pub async fn init_key_material_from_tappd_socket() -> Result<(), KeyManagerError> {
+    if try_get_key_material().is_some() {
+        warn!("Key material already initialized, keeping the existing key");
+        return Ok(());
+    }
 
+    let key_material = derive_key_material(false).await?;
 
-    // Set the global KEY_MATERIAL (only once)
-    KEY_MATERIAL
-        .set(key_material)
-        .map_err(|_| KeyManagerError {
-            message: "Key material already initialized".to_string(),
-        })?;
+    // Set the global KEY_MATERIAL (only once); a concurrent init that won the race keeps its key
+    let mut current = KEY_MATERIAL.write().unwrap();
+    if current.is_some() {
+        warn!("Key material was initialized concurrently, keeping the existing key");
+    } else {
+        *current = Some(Arc::new(key_material));
+    }
+
+    Ok(())
+}
 
+/// Re-derives the key material and atomically swaps it in, returning the previous and new keys.
+/// Requests that already fetched the previous key finish with it.
+pub async fn rotate_key_material() -> Result<(Option<Arc<KeyMaterial>>, Arc<KeyMaterial>), KeyManagerError> {
+    let _rotation = ROTATION_LOCK.lock().await;
+    let key_material = Arc::new(derive_key_material(true).await?);
+    let previous = KEY_MATERIAL.write().unwrap().replace(Arc::clone(&key_material));
+    info!(verifying_key = %key_material.encode_verify_key(), "Rotated key material");
+    Ok((previous, key_material))
+}
+
+/// Safe getter: returns the current KeyMaterial if already initialized
+pub fn try_get_key_material() -> Option<Arc<KeyMaterial>> {
+    KEY_MATERIAL.read().unwrap().clone()
+}
+
+/// Loads the operator's offline signing key from `TLSN_VERIFIER_OPERATOR_KEY_PATH`, if set.
+/// Fails if the file is configured but cannot be read or is not a P-256 PKCS#8 PEM key.
+/// Idempotent like the enclave key: an already loaded key is kept.
+pub fn init_operator_key() -> Result<(), KeyManagerError> {
+    let Some(path) = config::get_operator_key_path() else {
+        info!("No operator key configured");
+        return Ok(());
+    };
+    if OPERATOR_KEY.get().is_some() {
+        warn!("Operator key already initialized, keeping the existing key");
+        return Ok(());
+    }
+    let pem = fs::read_to_string(&path).map_err(|e| KeyManagerError {
+        message: format!("Failed to read operator key {}: {}", path, e),
+    })?;
+    let key_material = KeyMaterial::from_operator_pem(&pem).map_err(|e| KeyManagerError {
+        message: format!("Invalid operator key in {}: {}", path, e),
+    })?;
+    info!(path = %path, verifying_key = %key_material.encode_verify_key(), "Loaded operator key");
+    let _ = OPERATOR_KEY.set(key_material);
     Ok(())
 }
 
-/// Safe getter: returns `Some(&KeyMaterial)` if already initialized
-pub fn try_get_key_material() -> Option<&'static KeyMaterial> {
-    KEY_MATERIAL.get()
+/// Safe getter: returns the operator's offline key if one was loaded
+pub fn try_get_operator_key() -> Option<&'static KeyMaterial> {
+    OPERATOR_KEY.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a key file path unique to this test run, removed when dropped
+    struct TempKeyFile(String);
+
+    impl TempKeyFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tlsn-verifier-{}-{}.json", name, std::process::id()));
+            let _ = fs::remove_file(&path);
+            TempKeyFile(path.to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempKeyFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn persisted_key_survives_a_restart() {
+        let file = TempKeyFile::new("restart");
+        let first = reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).unwrap();
+        // A restart generates a new random key, but the persisted one is reloaded instead
+        let restarted = reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).unwrap();
+        assert_eq!(restarted.encode_verify_key(), first.encode_verify_key());
+        assert_eq!(restarted.signing_key.to_bytes(), first.signing_key.to_bytes());
+        assert!(matches!(restarted.source, KeySource::Random));
+        // The secret is not stored in the clear
+        let contents = fs::read_to_string(&file.0).unwrap();
+        assert!(!contents.contains(&hex::encode(first.signing_key.to_bytes())));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&file.0).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn rotation_replaces_the_persisted_key() {
+        let file = TempKeyFile::new("rotation");
+        let first = reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).unwrap();
+        let rotated = reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), true).unwrap();
+        assert_ne!(rotated.encode_verify_key(), first.encode_verify_key());
+        let restarted = reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).unwrap();
+        assert_eq!(restarted.encode_verify_key(), rotated.encode_verify_key());
+    }
+
+    #[test]
+    fn key_file_rejects_wrong_passphrase_and_tampering() {
+        let file = TempKeyFile::new("tamper");
+        reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).unwrap();
+        assert!(load_key_file(&file.0, "other passphrase").is_err());
+
+        let mut key_file: EncryptedKeyFile = serde_json::from_str(&fs::read_to_string(&file.0).unwrap()).unwrap();
+        let mut ciphertext = hex::decode(&key_file.ciphertext_hex_encoded).unwrap();
+        ciphertext[0] ^= 1;
+        key_file.ciphertext_hex_encoded = hex::encode(ciphertext);
+        fs::write(&file.0, serde_json::to_string(&key_file).unwrap()).unwrap();
+        assert!(load_key_file(&file.0, "passphrase").is_err());
+        // A key file that cannot be decrypted stops the restart rather than being replaced
+        assert!(reload_or_persist_key(&file.0, "passphrase", KeyMaterial::new_random(), false).is_err());
+    }
 }
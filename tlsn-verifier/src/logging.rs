@@ -0,0 +1,79 @@
+use crate::access_log::{ACCESS_LOG_TARGET, SUMMARY_LOG_TARGET};
+use crate::config;
+use std::fmt;
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::{filter_fn, EnvFilter, FilterExt};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+/// Returns whether events for `target` are access log or verification summary lines,
+/// which are written as-is instead of as diagnostic JSON
+fn is_access_target(target: &str) -> bool {
+    target == ACCESS_LOG_TARGET || target == SUMMARY_LOG_TARGET
+}
+
+/// Parses a comma-separated list of `level` and `target=level` directives,
+/// e.g. `info,tlsn_verifier::verifier=debug`; targets without a directive log at `info`
+fn parse_filter(spec: &str) -> Result<EnvFilter, String> {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .parse(spec)
+        .map_err(|e| format!("Invalid log filter '{}': {}", spec, e))
+}
+
+/// Writes only an event's message, for access log and summary lines that are already formatted
+struct MessageOnly;
+
+impl<S, N> FormatEvent<S, N> for MessageOnly
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// Installs the stdout log subscriber: diagnostic events as one JSON object per line, filtered
+/// by `TLSN_VERIFIER_LOG` (falling back to `RUST_LOG`, then `info`), and access log and
+/// verification summary lines unchanged, whatever the filter.
+/// Fails if the filter is invalid or another subscriber is already installed.
+pub fn init_logging() -> Result<(), String> {
+    let filter = parse_filter(&config::get_log_filter())?;
+    let diagnostics = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(true)
+        .with_filter(filter.and(filter_fn(|metadata| !is_access_target(metadata.target()))));
+    let access = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .event_format(MessageOnly)
+        .with_filter(filter_fn(|metadata| is_access_target(metadata.target())));
+    let subscriber = Registry::default().with(diagnostics).with(access);
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| format!("Cannot install log subscriber: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_default_level_and_target_overrides() {
+        for spec in ["info", "warn,tlsn_verifier::verifier=debug", "tlsn_verifier::callback=trace"] {
+            assert!(parse_filter(spec).is_ok(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_levels() {
+        for spec in ["tlsn_verifier=verbose", "warn,tlsn_verifier::verifier=loud"] {
+            assert!(parse_filter(spec).is_err(), "{}", spec);
+        }
+    }
+}
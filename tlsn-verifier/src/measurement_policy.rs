@@ -0,0 +1,110 @@
+use crate::config;
+use crate::quote::TdMeasurements;
+use crate::types::AttestationError;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::fs;
+use tracing::info;
+
+/// Singleton holding the measurement policy, if one is configured
+static MEASUREMENT_POLICY: OnceCell<MeasurementPolicy> = OnceCell::new();
+
+/// Acceptable enclave measurement values, loaded from a JSON policy file.
+/// Each list is optional; an absent list places no constraint on that measurement.
+/// Unknown fields are rejected, so a misspelt list cannot silently disable a check.
+#[derive(Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MeasurementPolicy {
+    pub mrtd: Option<Vec<String>>,
+    pub rtmr0: Option<Vec<String>>,
+    pub rtmr1: Option<Vec<String>>,
+    pub rtmr2: Option<Vec<String>>,
+    pub rtmr3: Option<Vec<String>>,
+}
+
+impl MeasurementPolicy {
+    /// Loads a policy from a JSON file
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path, e))
+    }
+
+    /// Checks the measurements signed in the enclave's quote against the policy,
+    /// failing with `MEASUREMENT_NOT_ALLOWED` on the first mismatch
+    pub fn check(&self, measurements: &TdMeasurements) -> Result<(), AttestationError> {
+        let measurements = [
+            ("mrtd", &self.mrtd, &measurements.mrtd),
+            ("rtmr0", &self.rtmr0, &measurements.rtmrs[0]),
+            ("rtmr1", &self.rtmr1, &measurements.rtmrs[1]),
+            ("rtmr2", &self.rtmr2, &measurements.rtmrs[2]),
+            ("rtmr3", &self.rtmr3, &measurements.rtmrs[3]),
+        ];
+        for (name, allowed, actual) in measurements {
+            let Some(allowed) = allowed else { continue };
+            let actual = hex::encode(actual);
+            if !allowed.iter().any(|value| normalize_hex(value) == actual) {
+                return Err(AttestationError::with_code(
+                    "MEASUREMENT_NOT_ALLOWED",
+                    format!("Measurement {} '{}' is not allowed by the measurement policy", name, actual),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercases a hex string and strips an optional `0x` prefix
+fn normalize_hex(value: &str) -> String {
+    let value = value.trim();
+    value.strip_prefix("0x").unwrap_or(value).to_lowercase()
+}
+
+/// Loads the measurement policy from `TLSN_VERIFIER_MEASUREMENT_POLICY_PATH`, if set.
+/// Fails if the file is configured but cannot be read or parsed.
+pub fn init_measurement_policy() -> Result<(), String> {
+    let Some(path) = config::get_measurement_policy_path() else {
+        info!("No measurement policy configured");
+        return Ok(());
+    };
+    let policy = MeasurementPolicy::load_from_file(&path)?;
+    info!(path = %path, "Loaded measurement policy");
+    MEASUREMENT_POLICY
+        .set(policy)
+        .map_err(|_| "Measurement policy already initialized".to_string())
+}
+
+/// Safe getter: returns the measurement policy if one is configured
+pub fn try_get_measurement_policy() -> Option<&'static MeasurementPolicy> {
+    MEASUREMENT_POLICY.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurements() -> TdMeasurements {
+        TdMeasurements { mrtd: [0xaa; 48], rtmrs: [[0x00; 48], [0x01; 48], [0x02; 48], [0x03; 48]] }
+    }
+
+    #[test]
+    fn listed_measurements_are_allowed() {
+        let policy = MeasurementPolicy {
+            mrtd: Some(vec![format!("0x{}", "AA".repeat(48))]),
+            rtmr3: Some(vec!["ff".repeat(48), "03".repeat(48)]),
+            ..Default::default()
+        };
+        assert!(policy.check(&measurements()).is_ok());
+    }
+
+    #[test]
+    fn unlisted_measurement_is_rejected() {
+        let policy = MeasurementPolicy { rtmr1: Some(vec!["02".repeat(48)]), ..Default::default() };
+        let err = policy.check(&measurements()).unwrap_err();
+        assert_eq!(err.code.as_deref(), Some("MEASUREMENT_NOT_ALLOWED"));
+    }
+
+    #[test]
+    fn unknown_policy_fields_are_rejected() {
+        assert!(serde_json::from_str::<MeasurementPolicy>(r#"{"mr_aggregated": ["00"]}"#).is_err());
+    }
+}
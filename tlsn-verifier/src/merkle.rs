@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separation prefix for leaf hashes
+const LEAF_PREFIX: u8 = 0x00;
+/// Domain separation prefix for interior node hashes
+const NODE_PREFIX: u8 = 0x01;
+
+/// Side on which a sibling hash sits relative to the running hash
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiblingPosition {
+    Left,
+    Right,
+}
+
+/// One step of an inclusion proof: the sibling hash and which side it sits on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub sibling_hex_encoded: String,
+    pub position: SiblingPosition,
+}
+
+/// Hashes a leaf: SHA-256(0x00 || data)
+pub fn hash_leaf(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Hashes two child nodes: SHA-256(0x01 || left || right)
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Computes the next tree level; an unpaired last node is promoted unchanged
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Computes the Merkle root over already-hashed leaves. Returns `None` for an empty set.
+pub fn root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if leaves.is_empty() {
+        return None;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    Some(level[0])
+}
+
+/// Builds the inclusion proof for the leaf at `index`, ordered from the leaf up to the root
+pub fn proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut steps = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        let sibling = if index % 2 == 0 { index + 1 } else { index - 1 };
+        if let Some(hash) = level.get(sibling) {
+            steps.push(ProofStep {
+                sibling_hex_encoded: hex::encode(hash),
+                position: if sibling < index { SiblingPosition::Left } else { SiblingPosition::Right },
+            });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(steps)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Recomputes the root from a leaf hash and its inclusion proof
+    pub(crate) fn root_from_proof(leaf: &[u8; 32], steps: &[ProofStep]) -> [u8; 32] {
+        steps.iter().fold(*leaf, |hash, step| {
+            let sibling: [u8; 32] = hex::decode(&step.sibling_hex_encoded).unwrap().try_into().unwrap();
+            match step.position {
+                SiblingPosition::Left => hash_node(&sibling, &hash),
+                SiblingPosition::Right => hash_node(&hash, &sibling),
+            }
+        })
+    }
+
+    /// Leaf hashes of `a`, `b`, `c`, ... for a tree of `count` leaves
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        (0..count).map(|i| hash_leaf(&[b'a' + i as u8])).collect()
+    }
+
+    #[test]
+    fn roots_match_known_answers() {
+        let cases = [
+            (1, "022a6979e6dab7aa5ae4c3e5e45f7e977112a7e63593820dbec1ec738a24f93c"),
+            (2, "b137985ff484fb600db93107c77b0365c80d78f5b429ded0fd97361d077999eb"),
+            (3, "36642e73c2540ab121e3a6bf9545b0a24982cd830eb13d3cd19de3ce6c021ec1"),
+            (4, "33376a3bd63e9993708a84ddfe6c28ae58b83505dd1fed711bd924ec5a6239f0"),
+            (5, "fe14a5426fbd70c0fa73f52342afed0da0bd23c4838662ccf6b88a3070ead97b"),
+            (7, "4ae191939f548d9934740b88dea2c5cb89bb8870fc4505cd79dec6bbfaaee9cb"),
+        ];
+        for (count, expected) in cases {
+            assert_eq!(hex::encode(root(&leaves(count)).unwrap()), expected, "{} leaves", count);
+        }
+        assert_eq!(root(&[]), None);
+    }
+
+    #[test]
+    fn unpaired_last_leaf_is_promoted_unchanged() {
+        let l = leaves(5);
+        let first_four = hash_node(&hash_node(&l[0], &l[1]), &hash_node(&l[2], &l[3]));
+        assert_eq!(root(&l), Some(hash_node(&first_four, &l[4])));
+
+        // The promoted leaf only has a sibling at the top level
+        let steps = proof(&l, 4).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].position, SiblingPosition::Left);
+        assert_eq!(steps[0].sibling_hex_encoded, hex::encode(first_four));
+    }
+
+    #[test]
+    fn every_inclusion_proof_leads_to_the_root() {
+        for count in 1..=17 {
+            let l = leaves(count);
+            let expected = root(&l).unwrap();
+            for (index, leaf) in l.iter().enumerate() {
+                let steps = proof(&l, index).unwrap();
+                assert_eq!(root_from_proof(leaf, &steps), expected, "leaf {} of {}", index, count);
+                // The proof does not hold for another leaf
+                if count > 1 {
+                    let other = &l[(index + 1) % count];
+                    assert_ne!(root_from_proof(other, &steps), expected, "leaf {} of {}", index, count);
+                }
+            }
+            assert!(proof(&l, count).is_none());
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds (in seconds) of the latency histogram buckets
+const LATENCY_BUCKETS_SECS: [f64; 11] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Upper bounds (in bytes) of the resident memory histogram buckets
+const MEMORY_BUCKETS_BYTES: [f64; 8] = [
+    16.0 * 1024.0 * 1024.0,
+    32.0 * 1024.0 * 1024.0,
+    64.0 * 1024.0 * 1024.0,
+    128.0 * 1024.0 * 1024.0,
+    256.0 * 1024.0 * 1024.0,
+    512.0 * 1024.0 * 1024.0,
+    1024.0 * 1024.0 * 1024.0,
+    2048.0 * 1024.0 * 1024.0,
+];
+
+/// Time spent obtaining a quote, from queueing the request to receiving the provider's body
+pub static QUOTE_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "tlsn_verifier_quote_duration_seconds",
+        "Time spent obtaining a TDX quote, including time queued for the provider",
+        &LATENCY_BUCKETS_SECS,
+    )
+});
+
+/// Time spent producing a signed attestation (quote, measurement policy check and signing)
+pub static ATTESTATION_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "tlsn_verifier_attestation_duration_seconds",
+        "Time spent producing a signed attestation",
+        &LATENCY_BUCKETS_SECS,
+    )
+});
+
+/// Resident memory of the process sampled after each attestation (Linux only)
+pub static ATTESTATION_RESIDENT_MEMORY: Lazy<Histogram> = Lazy::new(|| {
+    Histogram::new(
+        "tlsn_verifier_attestation_resident_memory_bytes",
+        "Resident memory of the verifier process sampled after each attestation",
+        &MEMORY_BUCKETS_BYTES,
+    )
+});
+
+/// Per-attestation timings and resource usage, returned on `/verify-proof?debug=true`
+#[derive(Serialize, Clone, Debug)]
+pub struct AttestationMetrics {
+    pub quote_ms: f64,                       // Time spent obtaining the quote (including queueing)
+    pub attestation_ms: f64,                 // Total time spent producing the signed attestation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resident_memory_bytes: Option<u64>,  // Resident memory of the process afterwards, if available
+}
+
+impl AttestationMetrics {
+    /// Records the measurements into the process-wide histograms
+    pub fn record(&self) {
+        QUOTE_DURATION.observe(self.quote_ms / 1000.0);
+        ATTESTATION_DURATION.observe(self.attestation_ms / 1000.0);
+        if let Some(bytes) = self.resident_memory_bytes {
+            ATTESTATION_RESIDENT_MEMORY.observe(bytes as f64);
+        }
+    }
+}
+
+/// A cumulative histogram with fixed bucket bounds, safe to update from many requests at once
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    bounds: &'static [f64],
+    buckets: Vec<AtomicU64>,  // Non-cumulative count per bound; the last entry is `+Inf`
+    sum_micros: AtomicU64,    // Sum of observed values, scaled by 1e6 to stay integral
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new(name: &'static str, help: &'static str, bounds: &'static [f64]) -> Self {
+        Histogram {
+            name,
+            help,
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a single observation
+    pub fn observe(&self, value: f64) {
+        let index = self.bounds.iter().position(|bound| value <= *bound).unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add((value * 1_000_000.0) as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Appends the histogram in the Prometheus text exposition format
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        let mut cumulative = 0;
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", self.name, bound, cumulative);
+        }
+        cumulative += self.buckets[self.bounds.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", self.name, cumulative);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", self.name, sum);
+        let _ = writeln!(out, "{}_count {}", self.name, self.count.load(Ordering::Relaxed));
+    }
+}
+
+/// Converts a duration to fractional milliseconds
+pub fn as_millis_f64(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Reads the resident memory of the current process from `/proc/self/status`.
+/// Returns `None` where procfs is not available.
+pub fn resident_memory_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.trim_start_matches("VmRSS:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Renders all metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+    QUOTE_DURATION.render(&mut out);
+    ATTESTATION_DURATION.render(&mut out);
+    ATTESTATION_RESIDENT_MEMORY.render(&mut out);
+    out
+}
@@ -0,0 +1,153 @@
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use serde::Serialize;
+
+/// Serialization formats the verifier can respond with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    /// Returns the MIME type sent back in the `Content-Type` header
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Cbor => "application/cbor",
+            ResponseFormat::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// Maps a single media range from an `Accept` header to a supported format
+    fn from_media_range(media_range: &str) -> Option<Self> {
+        match media_range {
+            "application/json" | "application/*" | "*/*" => Some(ResponseFormat::Json),
+            "application/cbor" => Some(ResponseFormat::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(ResponseFormat::MessagePack)
+            }
+            _ => None,
+        }
+    }
+
+    /// Picks the preferred format from an `Accept` header value.
+    /// Defaults to JSON when the header is absent or empty.
+    /// Returns `None` when none of the offered media ranges can be satisfied.
+    pub fn from_accept(accept: Option<&str>) -> Option<Self> {
+        let accept = match accept.map(str::trim) {
+            Some(a) if !a.is_empty() => a,
+            _ => return Some(ResponseFormat::Json),
+        };
+
+        // Collect (quality, position, format) for every supported media range
+        let mut candidates: Vec<(f32, usize, ResponseFormat)> = accept
+            .split(',')
+            .enumerate()
+            .filter_map(|(position, entry)| {
+                let mut parts = entry.split(';');
+                let media_range = parts.next()?.trim().to_lowercase();
+                let quality = parts
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                let format = Self::from_media_range(&media_range)?;
+                (quality > 0.0).then_some((quality, position, format))
+            })
+            .collect();
+
+        // Highest quality wins; ties are broken by the order in the header
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        candidates.first().map(|(_, _, format)| *format)
+    }
+
+    /// Reads the `Accept` header of a request and picks the preferred format
+    pub fn from_request(req: &HttpRequest) -> Option<Self> {
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok());
+        Self::from_accept(accept)
+    }
+
+    /// Serializes a value into this format
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            ResponseFormat::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            ResponseFormat::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(value, &mut buf).map_err(|e| e.to_string())?;
+                Ok(buf)
+            }
+            ResponseFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Builds a response with the given status builder, serializing `value` in the negotiated format
+pub fn respond<T: Serialize>(
+    mut builder: HttpResponseBuilder,
+    format: ResponseFormat,
+    value: &T,
+) -> HttpResponse {
+    match format.serialize(value) {
+        Ok(bytes) => builder.content_type(format.content_type()).body(bytes),
+        Err(e) => HttpResponse::InternalServerError()
+            .body(format!("Failed to serialize response: {}", e)),
+    }
+}
+
+/// Response returned when the `Accept` header cannot be satisfied
+pub fn not_acceptable() -> HttpResponse {
+    HttpResponse::NotAcceptable()
+        .body("Supported formats: application/json, application/cbor, application/msgpack")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_selects_the_preferred_format() {
+        let cases: [(Option<&str>, Option<ResponseFormat>); 14] = [
+            // Absent or empty header
+            (None, Some(ResponseFormat::Json)),
+            (Some("  "), Some(ResponseFormat::Json)),
+            // Single media ranges, case-insensitive
+            (Some("application/cbor"), Some(ResponseFormat::Cbor)),
+            (Some("Application/X-MsgPack"), Some(ResponseFormat::MessagePack)),
+            // Wildcards fall back to JSON
+            (Some("*/*"), Some(ResponseFormat::Json)),
+            (Some("application/*"), Some(ResponseFormat::Json)),
+            // Highest quality wins, then order in the header
+            (Some("application/json;q=0.5, application/cbor"), Some(ResponseFormat::Cbor)),
+            (Some("application/cbor;q=0.2, application/msgpack;q=0.9"), Some(ResponseFormat::MessagePack)),
+            (Some("application/msgpack, application/cbor"), Some(ResponseFormat::MessagePack)),
+            (Some("*/*;q=0.1, application/cbor"), Some(ResponseFormat::Cbor)),
+            (Some("text/html, application/cbor ; q=0.8"), Some(ResponseFormat::Cbor)),
+            // q=0 excludes a media range; an unparsable q-value counts as 1
+            (Some("application/cbor;q=0, application/json;q=0.1"), Some(ResponseFormat::Json)),
+            (Some("application/cbor;q=high"), Some(ResponseFormat::Cbor)),
+            // Nothing supported
+            (Some("text/html, application/xml;q=0.9"), None),
+        ];
+        for (accept, expected) in cases {
+            assert_eq!(ResponseFormat::from_accept(accept), expected, "{:?}", accept);
+        }
+    }
+
+    #[test]
+    fn only_excluded_formats_are_not_acceptable() {
+        assert_eq!(ResponseFormat::from_accept(Some("application/json;q=0, */*;q=0")), None);
+        assert_eq!(not_acceptable().status(), actix_web::http::StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[test]
+    fn responses_carry_the_format_content_type() {
+        for format in [ResponseFormat::Json, ResponseFormat::Cbor, ResponseFormat::MessagePack] {
+            let res = respond(HttpResponse::Ok(), format, &serde_json::json!({"score": 742}));
+            assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), format.content_type());
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{web, Error};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use serde::Deserialize;
+use std::rc::Rc;
+
+/// Query parameter opting into pretty-printed JSON
+#[derive(Deserialize)]
+struct PrettyQuery {
+    #[serde(default)]
+    pretty: bool,
+}
+
+/// Middleware that pretty-prints JSON response bodies when the request has `?pretty=true`,
+/// so responses are readable when debugging with curl. Other formats are passed through.
+pub struct PrettyJson;
+
+impl<S, B> Transform<S, ServiceRequest> for PrettyJson
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = PrettyJsonMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    /// Called once during application startup to create the middleware
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(PrettyJsonMiddleware { service: Rc::new(service) })
+    }
+}
+
+/// Middleware logic re-formatting JSON responses
+pub struct PrettyJsonMiddleware<S> {
+    service: Rc<S>, // Wrapped service
+}
+
+impl<S, B> Service<ServiceRequest> for PrettyJsonMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    /// Polls if the service is ready to process requests
+    fn poll_ready(&self, ctx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    /// Forwards the request and, if requested, re-formats a JSON response body
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let pretty = web::Query::<PrettyQuery>::from_query(req.query_string()).is_ok_and(|q| q.pretty);
+        let srv = self.service.clone();
+
+        Box::pin(async move {
+            let res = srv.call(req).await?;
+            if !pretty || !is_json(&res) {
+                return Ok(res.map_into_boxed_body());
+            }
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let bytes = to_bytes(body)
+                .await
+                .map_err(|e| actix_web::error::ErrorInternalServerError(e.into().to_string()))?;
+            let body = prettify(&bytes).unwrap_or_else(|| bytes.to_vec());
+            Ok(ServiceResponse::new(req, res.set_body(body).map_into_boxed_body()))
+        })
+    }
+}
+
+/// Returns whether the response carries a JSON body (`application/json` or a `+json`/`vc` type)
+fn is_json<B>(res: &ServiceResponse<B>) -> bool {
+    res.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or("").trim().to_lowercase())
+        .is_some_and(|v| v == "application/json" || v == "application/vc" || v.ends_with("+json"))
+}
+
+/// Re-formats a JSON document with indentation, keeping its field order.
+/// Returns `None` if the bytes are not a single JSON document.
+fn prettify(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let mut out = Vec::new();
+    serde_transcode::transcode(&mut deserializer, &mut serde_json::Serializer::pretty(&mut out)).ok()?;
+    deserializer.end().ok()?;
+    Some(out)
+}
@@ -0,0 +1,131 @@
+/// Size of the TDX quote header preceding the TD report body
+const QUOTE_HEADER_LEN: usize = 48;
+/// Size of the body type/size descriptor that precedes the body in v5 quotes
+const V5_BODY_DESCRIPTOR_LEN: usize = 6;
+/// Offset of report_data within the TD report body
+/// (tee_tcb_svn, mr_seam, mr_signer_seam, attributes, xfam, mr_td, mr_config_id,
+/// mr_owner, mr_owner_config and rt_mr0..3 come first)
+const REPORT_DATA_OFFSET_IN_BODY: usize = 520;
+/// Offset of mr_td within the TD report body
+/// (tee_tcb_svn, mr_seam, mr_signer_seam, seam_attributes, td_attributes and xfam come first)
+const MR_TD_OFFSET_IN_BODY: usize = 136;
+/// Offset of rt_mr0 within the TD report body; rt_mr1..3 follow it
+/// (mr_config_id, mr_owner and mr_owner_config sit between mr_td and rt_mr0)
+const RTMR0_OFFSET_IN_BODY: usize = 328;
+/// Size of mr_td and of each rt_mr register (SHA-384)
+pub const MEASUREMENT_LEN: usize = 48;
+/// Size of the report_data field
+pub const REPORT_DATA_LEN: usize = 64;
+/// Size of the tee_tcb_svn field, the first field of the TD report body
+pub const TEE_TCB_SVN_LEN: usize = 16;
+
+/// Returns `len` bytes at `offset` within the TD report body of a raw TDX quote (versions 4 and 5)
+fn body_field<'a>(quote: &'a [u8], offset: usize, len: usize, name: &str) -> Result<&'a [u8], String> {
+    if quote.len() < 2 {
+        return Err("Quote is too short to contain a version".to_string());
+    }
+    let version = u16::from_le_bytes([quote[0], quote[1]]);
+    let body_offset = match version {
+        4 => QUOTE_HEADER_LEN,
+        5 => QUOTE_HEADER_LEN + V5_BODY_DESCRIPTOR_LEN,
+        other => return Err(format!("Unsupported quote version {}", other)),
+    };
+    let start = body_offset + offset;
+    quote
+        .get(start..start + len)
+        .ok_or_else(|| format!("Quote is too short ({} bytes) to contain {}", quote.len(), name))
+}
+
+/// Extracts the 64-byte report_data field from a raw TDX quote (versions 4 and 5)
+pub fn report_data_from_quote(quote: &[u8]) -> Result<[u8; REPORT_DATA_LEN], String> {
+    let bytes = body_field(quote, REPORT_DATA_OFFSET_IN_BODY, REPORT_DATA_LEN, "report_data")?;
+    let mut report_data = [0u8; REPORT_DATA_LEN];
+    report_data.copy_from_slice(bytes);
+    Ok(report_data)
+}
+
+/// Extracts the 16-byte tee_tcb_svn field (one SVN per TDX TCB component) from a raw TDX quote
+pub fn tee_tcb_svn_from_quote(quote: &[u8]) -> Result<[u8; TEE_TCB_SVN_LEN], String> {
+    let bytes = body_field(quote, 0, TEE_TCB_SVN_LEN, "tee_tcb_svn")?;
+    let mut svn = [0u8; TEE_TCB_SVN_LEN];
+    svn.copy_from_slice(bytes);
+    Ok(svn)
+}
+
+/// Measurements of the TD, as signed in its quote
+#[derive(Debug, Clone, PartialEq)]
+pub struct TdMeasurements {
+    pub mrtd: [u8; MEASUREMENT_LEN],       // Measurement of the initial TD contents
+    pub rtmrs: [[u8; MEASUREMENT_LEN]; 4], // Runtime measurement registers 0..3
+}
+
+/// Extracts mr_td and rt_mr0..3 from a raw TDX quote (versions 4 and 5)
+pub fn measurements_from_quote(quote: &[u8]) -> Result<TdMeasurements, String> {
+    let mut mrtd = [0u8; MEASUREMENT_LEN];
+    mrtd.copy_from_slice(body_field(quote, MR_TD_OFFSET_IN_BODY, MEASUREMENT_LEN, "mr_td")?);
+    let mut rtmrs = [[0u8; MEASUREMENT_LEN]; 4];
+    for (index, rtmr) in rtmrs.iter_mut().enumerate() {
+        let offset = RTMR0_OFFSET_IN_BODY + index * MEASUREMENT_LEN;
+        rtmr.copy_from_slice(body_field(quote, offset, MEASUREMENT_LEN, "rt_mr")?);
+    }
+    Ok(TdMeasurements { mrtd, rtmrs })
+}
+
+/// Decodes a hex-encoded quote, accepting an optional `0x` prefix
+pub fn decode_hex_quote(quote_hex: &str) -> Result<Vec<u8>, String> {
+    let quote_hex = quote_hex.trim();
+    hex::decode(quote_hex.strip_prefix("0x").unwrap_or(quote_hex))
+        .map_err(|e| format!("Quote is not valid hex: {}", e))
+}
+
+/// Decodes a hex-encoded quote (optionally `0x`-prefixed) and extracts its report_data
+pub fn report_data_from_hex_quote(quote_hex: &str) -> Result<[u8; REPORT_DATA_LEN], String> {
+    report_data_from_quote(&decode_hex_quote(quote_hex)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a version 4 quote whose TD report body bytes each equal their offset modulo 251
+    fn v4_quote() -> Vec<u8> {
+        let mut quote = vec![0u8; QUOTE_HEADER_LEN + REPORT_DATA_OFFSET_IN_BODY + REPORT_DATA_LEN];
+        quote[0] = 4;
+        for (offset, byte) in quote[QUOTE_HEADER_LEN..].iter_mut().enumerate() {
+            *byte = (offset % 251) as u8;
+        }
+        quote
+    }
+
+    fn expected(offset: usize) -> [u8; MEASUREMENT_LEN] {
+        std::array::from_fn(|i| ((offset + i) % 251) as u8)
+    }
+
+    #[test]
+    fn measurements_are_read_at_td_report_offsets() {
+        let measurements = measurements_from_quote(&v4_quote()).unwrap();
+        assert_eq!(measurements.mrtd, expected(136));
+        assert_eq!(measurements.rtmrs[0], expected(328));
+        assert_eq!(measurements.rtmrs[1], expected(376));
+        assert_eq!(measurements.rtmrs[2], expected(424));
+        assert_eq!(measurements.rtmrs[3], expected(472));
+    }
+
+    #[test]
+    fn v5_quotes_skip_the_body_descriptor() {
+        let v4 = v4_quote();
+        let mut v5 = v4[..QUOTE_HEADER_LEN].to_vec();
+        v5[0] = 5;
+        v5.extend_from_slice(&[0u8; V5_BODY_DESCRIPTOR_LEN]);
+        v5.extend_from_slice(&v4[QUOTE_HEADER_LEN..]);
+        assert_eq!(measurements_from_quote(&v5).unwrap(), measurements_from_quote(&v4).unwrap());
+        assert_eq!(report_data_from_quote(&v5).unwrap(), report_data_from_quote(&v4).unwrap());
+    }
+
+    #[test]
+    fn truncated_quote_is_rejected() {
+        let quote = v4_quote();
+        assert!(measurements_from_quote(&quote[..QUOTE_HEADER_LEN + 400]).is_err());
+        assert!(measurements_from_quote(&[4]).is_err());
+    }
+}
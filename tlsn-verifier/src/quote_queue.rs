@@ -0,0 +1,93 @@
+use crate::config;
+use crate::tappd_service;
+use crate::types::AttestationError;
+use hyper::body::Bytes;
+use once_cell::sync::OnceCell;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// A pending quote request and the channel its raw response body is sent back on
+struct QuoteJob {
+    custom_evidence: String,
+    reply: oneshot::Sender<Result<Bytes, AttestationError>>,
+}
+
+/// Sending side of the quote queue, set once the worker is running
+static QUOTE_QUEUE: OnceCell<mpsc::Sender<QuoteJob>> = OnceCell::new();
+
+/// Starts the single worker that forwards queued quote requests to the provider one at a time.
+/// Must be called from within a Tokio runtime.
+pub fn start_quote_queue() {
+    let capacity = config::get_quote_queue_capacity();
+    let (tx, mut rx) = mpsc::channel::<QuoteJob>(capacity);
+    if QUOTE_QUEUE.set(tx).is_err() {
+        warn!("Quote queue already started");
+        return;
+    }
+    tokio::spawn(async move {
+        while let Some(QuoteJob { custom_evidence, mut reply }) = rx.recv().await {
+            // Skip requests whose caller already gave up waiting
+            if reply.is_closed() {
+                continue;
+            }
+            // Abort the provider call if the caller goes away mid-request (e.g. the client
+            // disconnected and actix dropped the handler), freeing the provider for the next job.
+            // Dropping `fetch_quote` is safe: it only drops the in-flight socket request.
+            let result = tokio::select! {
+                result = fetch_quote(&custom_evidence) => result,
+                _ = reply.closed() => {
+                    debug!("Caller went away, aborted in-flight quote request");
+                    continue;
+                }
+            };
+            let _ = reply.send(result);
+        }
+    });
+    info!(capacity, "Quote queue started");
+}
+
+/// Sends one quote request to the provider and returns the full response body
+async fn fetch_quote(custom_evidence: &str) -> Result<Bytes, AttestationError> {
+    let res = tappd_service::send_quote_request(custom_evidence).await.map_err(|e| {
+        AttestationError {
+            code: None,
+            message: format!("Tappd Service Error: {}", e.message),
+        }
+    })?;
+    Ok(res.into_body())
+}
+
+/// Requests a quote through the queue and returns the provider's raw response body.
+/// Cancel-safe: dropping the returned future (e.g. on client disconnect) cancels the job,
+/// whether it is still queued or already in flight.
+/// Fails with `QUOTE_QUEUE_FULL` if the queue is at capacity, `QUOTE_WORKER_UNAVAILABLE` if its
+/// worker is not running, or `QUOTE_QUEUE_TIMEOUT`
+/// if no response arrives within the configured wait. Falls back to a direct
+/// provider call if the queue was never started.
+pub async fn request_quote(custom_evidence: &str) -> Result<Bytes, AttestationError> {
+    let Some(queue) = QUOTE_QUEUE.get() else {
+        return fetch_quote(custom_evidence).await;
+    };
+
+    let (reply, response) = oneshot::channel();
+    let job = QuoteJob { custom_evidence: custom_evidence.to_string(), reply };
+    queue.try_send(job).map_err(|e| match e {
+        mpsc::error::TrySendError::Full(_) => {
+            AttestationError::with_code("QUOTE_QUEUE_FULL", "Quote provider is busy, try again later")
+        }
+        mpsc::error::TrySendError::Closed(_) => {
+            AttestationError::with_code("QUOTE_WORKER_UNAVAILABLE", "Quote queue is not running")
+        }
+    })?;
+
+    let wait = Duration::from_millis(config::get_quote_queue_timeout_ms());
+    match tokio::time::timeout(wait, response).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(AttestationError::with_code("QUOTE_WORKER_UNAVAILABLE", "Quote queue worker stopped")),
+        Err(_) => Err(AttestationError::with_code(
+            "QUOTE_QUEUE_TIMEOUT",
+            format!("Timed out after {:?} waiting for the quote provider", wait),
+        )),
+    }
+}
@@ -0,0 +1,52 @@
+use crate::config;
+use jsonschema::Validator;
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+use std::fs;
+use tracing::info;
+
+/// Singleton holding the compiled response body schema, if one is configured
+static RESPONSE_SCHEMA: OnceCell<Validator> = OnceCell::new();
+
+/// Loads and compiles a JSON Schema from a file.
+/// Fails if the file cannot be read, is not JSON, or is not a valid schema.
+fn load_from_file(path: &str) -> Result<Validator, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let schema: Value = serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+    jsonschema::validator_for(&schema).map_err(|e| format!("Invalid JSON Schema in {}: {}", path, e))
+}
+
+/// Loads the response body schema from `TLSN_VERIFIER_RESPONSE_SCHEMA_PATH`, if set.
+/// Fails if the file is configured but cannot be compiled.
+pub fn init_response_schema() -> Result<(), String> {
+    let Some(path) = config::get_response_schema_path() else {
+        info!("No response schema configured");
+        return Ok(());
+    };
+    let validator = load_from_file(&path)?;
+    info!(path = %path, "Loaded response schema");
+    RESPONSE_SCHEMA
+        .set(validator)
+        .map_err(|_| "Response schema already initialized".to_string())
+}
+
+/// Returns whether a response schema is configured
+pub fn is_configured() -> bool {
+    RESPONSE_SCHEMA.get().is_some()
+}
+
+/// Validates a response body against the configured schema.
+/// Returns every violation as `<instance path>: <message>`; passes if no schema is configured.
+pub fn validate(body: &Value) -> Result<(), Vec<String>> {
+    let Some(validator) = RESPONSE_SCHEMA.get() else {
+        return Ok(());
+    };
+    let errors: Vec<String> = validator
+        .iter_errors(body)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            format!("{}: {}", if path.is_empty() { "/" } else { &path }, e)
+        })
+        .collect();
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
@@ -0,0 +1,325 @@
+use crate::config;
+use crate::types::VerificationResult;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tracing::{info, warn};
+
+/// Singleton holding the configured result cache backend
+static RESULT_CACHE: OnceCell<Box<dyn ResultCacheBackend>> = OnceCell::new();
+
+/// Storage backend for successful verification results, keyed by presentation hash
+pub trait ResultCacheBackend: Send + Sync {
+    /// Returns the cached result for `key` if present and not expired
+    fn get(&self, key: &str) -> Option<VerificationResult>;
+
+    /// Stores `result` under `key` for the configured TTL
+    fn put(&self, key: String, result: VerificationResult);
+
+    /// Removes the entry for `key`, returning how many entries were evicted (0 or 1)
+    fn invalidate(&self, key: &str) -> usize;
+
+    /// Removes all entries, returning how many were evicted
+    fn clear(&self) -> usize;
+}
+
+/// A cached verification result together with its expiry (Unix seconds)
+#[derive(Serialize, Deserialize, Clone)]
+struct CacheEntry {
+    result: VerificationResult,
+    expires_at: i64,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Default backend keeping results in process memory only
+pub struct InMemoryBackend {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    ttl_secs: i64,
+}
+
+impl InMemoryBackend {
+    pub fn new(ttl_secs: i64) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl_secs,
+        }
+    }
+}
+
+impl ResultCacheBackend for InMemoryBackend {
+    fn get(&self, key: &str) -> Option<VerificationResult> {
+        let now = chrono::Utc::now().timestamp();
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if !entry.is_expired(now) => Some(entry.result.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put(&self, key: String, result: VerificationResult) {
+        let now = chrono::Utc::now().timestamp();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        entries.insert(key, CacheEntry { result, expires_at: now + self.ttl_secs });
+    }
+
+    fn invalidate(&self, key: &str) -> usize {
+        self.entries.lock().unwrap().remove(key).map_or(0, |_| 1)
+    }
+
+    fn clear(&self) -> usize {
+        self.entries.lock().unwrap().drain().count()
+    }
+}
+
+/// Persistent backend storing results in a JSON file so they survive restarts.
+/// Changes are written by a background thread, off the request path: it rewrites the whole file
+/// atomically (temporary file, fsync, rename), coalescing changes made while it was writing.
+pub struct JsonFileBackend {
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    ttl_secs: i64,
+    changes: Option<Sender<()>>,   // Wakes the writer; dropped on shutdown so it exits
+    writer: Option<JoinHandle<()>>,
+}
+
+impl JsonFileBackend {
+    /// Opens the cache file at `path`, loading any unexpired entries it contains, and starts its
+    /// writer. A file that cannot be parsed is moved aside to `<path>.corrupt` and the cache starts empty.
+    pub fn open(path: PathBuf, ttl_secs: i64) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        let mut entries: HashMap<String, CacheEntry> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                let corrupt_path = path.with_extension("corrupt");
+                warn!(path = ?path, moved_to = ?corrupt_path, error = %e, "Ignoring unreadable cache file");
+                if let Err(e) = fs::rename(&path, &corrupt_path) {
+                    warn!(path = ?path, error = %e, "Failed to move unreadable cache file aside");
+                }
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        entries.retain(|_, entry| !entry.is_expired(now));
+        info!(entries = entries.len(), path = ?path, "Loaded cached results");
+
+        let entries = Arc::new(Mutex::new(entries));
+        let (changes, pending) = mpsc::channel::<()>();
+        let writer_entries = Arc::clone(&entries);
+        let writer = std::thread::spawn(move || {
+            while pending.recv().is_ok() {
+                // Changes that arrived meanwhile are covered by this write
+                while pending.try_recv().is_ok() {}
+                let snapshot = serde_json::to_vec(&*writer_entries.lock().unwrap());
+                let written = snapshot.map_err(std::io::Error::from).and_then(|bytes| write_atomically(&path, &bytes));
+                if let Err(e) = written {
+                    warn!(path = ?path, error = %e, "Failed to persist cache");
+                }
+            }
+        });
+        Self {
+            entries,
+            ttl_secs,
+            changes: Some(changes),
+            writer: Some(writer),
+        }
+    }
+
+    /// Schedules a write of the current entries
+    fn persist(&self) {
+        if let Some(changes) = &self.changes {
+            let _ = changes.send(());
+        }
+    }
+}
+
+impl Drop for JsonFileBackend {
+    /// Stops the writer after it has written any pending changes
+    fn drop(&mut self) {
+        self.changes.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Replaces the file at `path` with `bytes`, so readers and restarts never see a partial file:
+/// writes a temporary file next to it, syncs it to disk, renames it over `path` and syncs the directory
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+impl ResultCacheBackend for JsonFileBackend {
+    fn get(&self, key: &str) -> Option<VerificationResult> {
+        let now = chrono::Utc::now().timestamp();
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|entry| !entry.is_expired(now))
+            .map(|entry| entry.result.clone())
+    }
+
+    fn put(&self, key: String, result: VerificationResult) {
+        let now = chrono::Utc::now().timestamp();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        entries.insert(key, CacheEntry { result, expires_at: now + self.ttl_secs });
+        self.persist();
+    }
+
+    fn invalidate(&self, key: &str) -> usize {
+        if self.entries.lock().unwrap().remove(key).is_none() {
+            return 0;
+        }
+        self.persist();
+        1
+    }
+
+    fn clear(&self) -> usize {
+        let evicted = self.entries.lock().unwrap().drain().count();
+        self.persist();
+        evicted
+    }
+}
+
+/// Initializes the global result cache from configuration.
+/// Uses the JSON file backend if `TLSN_VERIFIER_RESULT_CACHE_PATH` is set, in-memory otherwise.
+/// A TTL of `0` disables caching entirely.
+pub fn init_result_cache() {
+    let ttl_secs = config::get_result_cache_ttl_secs();
+    if ttl_secs <= 0 {
+        info!("Result cache disabled");
+        return;
+    }
+    let backend: Box<dyn ResultCacheBackend> = match config::get_result_cache_path() {
+        Some(path) => Box::new(JsonFileBackend::open(PathBuf::from(path), ttl_secs)),
+        None => Box::new(InMemoryBackend::new(ttl_secs)),
+    };
+    if RESULT_CACHE.set(backend).is_err() {
+        warn!("Result cache already initialized");
+    }
+}
+
+/// Safe getter: returns the result cache if it has been initialized
+pub fn try_get_result_cache() -> Option<&'static dyn ResultCacheBackend> {
+    RESULT_CACHE.get().map(|backend| backend.as_ref())
+}
+
+/// Computes the cache key for a presentation: hex-encoded SHA-256 of the request body
+pub fn presentation_hash(body: &str) -> String {
+    hex::encode(Sha256::digest(body.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cache file in the temp directory, removed (with its side files) when dropped
+    struct TempCacheFile(PathBuf);
+
+    impl TempCacheFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tlsn-verifier-cache-{}-{}.json", name, std::process::id()));
+            let file = TempCacheFile(path);
+            file.remove();
+            file
+        }
+
+        fn remove(&self) {
+            for path in [self.0.clone(), self.0.with_extension("tmp"), self.0.with_extension("corrupt")] {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    impl Drop for TempCacheFile {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    fn result(score: &str) -> VerificationResult {
+        VerificationResult {
+            is_valid: true,
+            server_name: "example.com".to_string(),
+            score: score.to_string(),
+            verifying_key: "02ab".to_string(),
+            sent_hex_encoded: String::new(),
+            sent_readable: String::new(),
+            recv_hex_encoded: String::new(),
+            recv_readable: String::new(),
+            time: "2026-01-01T00:00:00+00:00".to_string(),
+            result_id: String::new(),
+            response_status: 200,
+            provenance: Default::default(),
+            required_min_score: None,
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn file_backend_round_trips_across_a_restart() {
+        let file = TempCacheFile::new("round-trip");
+        let backend = JsonFileBackend::open(file.0.clone(), 300);
+        backend.put("a".to_string(), result("742"));
+        backend.put("b".to_string(), result("650"));
+        assert_eq!(backend.invalidate("b"), 1);
+        // Dropping the backend waits for its writer, like a shutdown
+        drop(backend);
+
+        let reopened = JsonFileBackend::open(file.0.clone(), 300);
+        assert_eq!(reopened.get("a").map(|r| r.score), Some("742".to_string()));
+        assert!(reopened.get("b").is_none());
+        assert!(!file.0.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned_or_reloaded() {
+        let memory = InMemoryBackend::new(0);
+        memory.put("a".to_string(), result("742"));
+        assert!(memory.get("a").is_none());
+
+        let file = TempCacheFile::new("expiry");
+        let backend = JsonFileBackend::open(file.0.clone(), 0);
+        backend.put("a".to_string(), result("742"));
+        assert!(backend.get("a").is_none());
+        drop(backend);
+        assert!(JsonFileBackend::open(file.0.clone(), 300).get("a").is_none());
+    }
+
+    #[test]
+    fn corrupt_file_is_moved_aside_and_replaced() {
+        let file = TempCacheFile::new("corrupt");
+        fs::write(&file.0, b"{\"a\": {\"result\": tru").unwrap();
+        let backend = JsonFileBackend::open(file.0.clone(), 300);
+        assert!(backend.get("a").is_none());
+        assert_eq!(fs::read(file.0.with_extension("corrupt")).unwrap(), b"{\"a\": {\"result\": tru");
+
+        backend.put("a".to_string(), result("742"));
+        drop(backend);
+        let reopened = JsonFileBackend::open(file.0.clone(), 300);
+        assert_eq!(reopened.get("a").map(|r| r.score), Some("742".to_string()));
+    }
+}
@@ -1,61 +1,1199 @@
-use actix_web::{get, post, HttpResponse, Responder};
+use actix_web::http::{header, StatusCode};
+use actix_web::{get, post, route, routes, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use futures_util::stream::{self, StreamExt};
 use serde_json;
-use crate::attestation::{get_attestation_report_with_signature};
-use crate::verifier::verify_proof;
-use crate::types::VerificationResponse;
+use crate::access_log::{self, log_verification_summary};
+use crate::auth::ApiKeyId;
+use crate::attestation::{build_attestation_bundle, build_envelope, clear_quote_cache, get_attestation_report_with_signature, sign_payload};
+use crate::callback::{deliver_callback, reserve_callback_slot, validate_callback_url};
+use crate::batch_store::{self, BatchState};
+use crate::collateral;
+use crate::config;
+use crate::crypto_provider::get_crypto_provider;
+use crate::credential::{did_document, issue_credential, DID_DOCUMENT_CONTENT_TYPE, VC_CONTENT_TYPE};
+use crate::deadline::Deadline;
+use crate::server_policy;
+use crate::error_disclosure::{public_attestation_error, public_verification_error};
+use crate::key_manager::{rotate_key_material, try_get_key_material};
+use crate::merkle;
+use crate::metrics;
+use crate::negotiation::{not_acceptable, respond, ResponseFormat};
+use crate::result_cache::{presentation_hash, try_get_result_cache};
+use crate::verifier::{require_min_score, response_field, verify_proof};
+use crate::tappd_service;
+use crate::session_store::{resolve_session_ref, store_base_session};
+use crate::subject_store;
+use tracing::{debug, info, trace, warn};
+use crate::types::{
+    AttestDigestRequest, AttestationError, BatchAttestationMode, BatchItem, BatchPageResponse, BatchStatus, BatchVerificationResponse, CompareKeyRequest,
+    CompareKeyResponse, CorrelateRequest, CorrelationResponse, CorrelationResult, HeartbeatResponse, InclusionProofResponse, InvalidateCacheRequest, InvalidateCacheResponse, PubKeyResponse, RotateKeyResponse, SessionResponse, SignedAttestation, SIGNATURE_NONCE_POLICY, VerificationError,
+    VerificationResponse, VerificationResult, VerificationSummary, VersionResponse,
+};
 use sha2::{Digest, Sha512};
-/// Health check endpoint for readiness/liveness probes
-#[get("/health")]
+/// Methods allowed on the read-only endpoints, advertised in OPTIONS responses
+const READ_ONLY_ALLOW: &str = "GET, HEAD, OPTIONS";
+
+/// Builds the response to an OPTIONS request on a read-only endpoint
+fn read_only_options() -> HttpResponse {
+    HttpResponse::NoContent()
+        .insert_header((header::ALLOW, READ_ONLY_ALLOW))
+        .finish()
+}
+
+/// Health check endpoint for readiness/liveness probes.
+/// When collateral refresh is configured, also reports collateral freshness as JSON.
+/// HEAD returns the same headers without a body.
+#[route("/health", method = "GET", method = "HEAD")]
 pub async fn health_check() -> impl Responder {
-    HttpResponse::Ok().body("OK") // Always returns 200 OK with simple body
+    if config::get_collateral_url().is_none() {
+        return HttpResponse::Ok().body("OK"); // Always returns 200 OK with simple body
+    }
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "OK",
+        "collateral": collateral::collateral_health(),
+    }))
+}
+
+/// Advertises the methods allowed on `/health`
+#[route("/health", method = "OPTIONS")]
+pub async fn health_options() -> impl Responder {
+    read_only_options()
+}
+
+/// Liveness endpoint: confirms the process is serving requests.
+/// Never touches external dependencies, so a tappd or collateral outage cannot fail it.
+#[route("/livez", method = "GET", method = "HEAD")]
+pub async fn livez() -> impl Responder {
+    HttpResponse::Ok().body("OK")
+}
+
+/// Readiness endpoint: returns 200 only if key material is initialized, tappd is reachable
+/// and the TDX collateral (if configured) is fresh; otherwise 503 listing the failed checks.
+#[route("/readyz", method = "GET", method = "HEAD")]
+pub async fn readyz() -> impl Responder {
+    let mut failures = Vec::new();
+    if try_get_key_material().is_none() {
+        failures.push("key material not initialized");
+    }
+    if !tappd_service::is_reachable().await {
+        failures.push("tappd not reachable");
+    }
+    if config::get_collateral_url().is_some() && collateral::collateral_health().stale {
+        failures.push("TDX collateral missing or stale");
+    }
+    if failures.is_empty() {
+        HttpResponse::Ok().json(serde_json::json!({ "status": "ready" }))
+    } else {
+        HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not_ready",
+            "failures": failures,
+        }))
+    }
+}
+
+/// Metrics endpoint: attestation latency and memory histograms in the Prometheus text format
+#[get("/metrics")]
+pub async fn metrics_route() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render())
+}
+
+/// Returns this verifier's public signing key and how it was provisioned.
+/// HEAD returns the same headers without a body.
+#[route("/pubkey", method = "GET", method = "HEAD")]
+pub async fn pubkey_route() -> impl Responder {
+    match try_get_key_material() {
+        Some(key_material) => HttpResponse::Ok().json(PubKeyResponse {
+            verifying_key_hex_encoded: key_material.encode_verify_key(),
+            scheme: key_material.scheme,
+            key_source: key_material.source.clone(),
+            verifying_key_certificate_chain: key_material.certificate_chain.clone(),
+            signature_nonce_policy: SIGNATURE_NONCE_POLICY,
+        }),
+        None => HttpResponse::InternalServerError().body("Key material not initialized"),
+    }
+}
+
+/// Advertises the methods allowed on `/pubkey`
+#[route("/pubkey", method = "OPTIONS")]
+pub async fn pubkey_options() -> impl Responder {
+    read_only_options()
+}
+
+/// Signs the current timestamp with the enclave key, so monitors can check that the
+/// signing key is live and correct rather than just that the process is up
+#[get("/heartbeat")]
+pub async fn heartbeat_route() -> impl Responder {
+    let Some(key_material) = try_get_key_material() else {
+        return HttpResponse::InternalServerError().body("Key material not initialized");
+    };
+    let timestamp = chrono::Utc::now().timestamp();
+    let message = format!("tlsn-verifier heartbeat {}", timestamp);
+    let signing_mode = config::get_signing_mode();
+    match sign_payload(&key_material, &hex::encode(message.as_bytes()), signing_mode) {
+        Ok(signed) => HttpResponse::Ok().json(HeartbeatResponse {
+            timestamp,
+            message,
+            signature_hex_encoded: signed.signature,
+            verifying_key_hex_encoded: signed.encoded_key,
+            signing_mode,
+            algorithm: signing_mode.algorithm(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(e),
+    }
+}
+
+/// Returns the verifier build version and the accepted TLSN core version.
+/// HEAD returns the same headers without a body.
+#[route("/version", method = "GET", method = "HEAD")]
+pub async fn version_route() -> impl Responder {
+    HttpResponse::Ok().json(VersionResponse {
+        name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        accepted_tlsn_core_version: config::get_tlsn_core_version(),
+        accepted_tlsn_core_versions: config::get_accepted_versions(),
+        accepted_tlsn_core_version_range: config::get_version_range().map(|range| range.to_string()),
+    })
+}
+
+/// Returns the DID document of the verifier's `did:key` (the issuer of `?format=vc` credentials).
+/// Served without an API key since it only holds public key material.
+#[get("/.well-known/did.json")]
+pub async fn did_document_route() -> impl Responder {
+    match try_get_key_material() {
+        Some(key_material) => HttpResponse::Ok()
+            .content_type(DID_DOCUMENT_CONTENT_TYPE)
+            .body(did_document(&key_material).to_string()),
+        None => HttpResponse::InternalServerError().body("Key material not initialized"),
+    }
+}
+
+/// Advertises the methods allowed on `/version`
+#[route("/version", method = "OPTIONS")]
+pub async fn version_options() -> impl Responder {
+    read_only_options()
+}
+
+/// Resolves any `session_ref` in the body and verifies the resulting presentation,
+/// reusing a cached result for the same presentation if available. The result is then checked
+/// against the latest proof recorded for its subject (if configured), also when it was cached.
+/// Step timings are kept only if `keep_timings` is set (and never cached).
+fn verify_presentation_body(
+    body: &str,
+    deadline: &Deadline,
+    keep_timings: bool,
+) -> Result<VerificationResult, VerificationError> {
+    let result = verify_presentation_body_cached(body, deadline, keep_timings)?;
+    subject_store::check(&result).map_err(public_verification_error)?;
+    Ok(result)
+}
+
+/// Verifies the presentation in `body`, or returns the cached result for it
+fn verify_presentation_body_cached(
+    body: &str,
+    deadline: &Deadline,
+    keep_timings: bool,
+) -> Result<VerificationResult, VerificationError> {
+    let body = resolve_session_ref(body).map_err(public_verification_error)?;
+    let cache = try_get_result_cache();
+    let cache_key = presentation_hash(&body);
+    if let Some(cached) = cache.and_then(|c| c.get(&cache_key)) {
+        debug!("Using cached verification result");
+        return Ok(cached);
+    }
+    let mut result = verify_proof(&body, get_crypto_provider(), deadline).map_err(public_verification_error);
+    if let Ok(verified) = &mut result {
+        let timings = verified.timings.take();
+        if let Some(cache) = cache {
+            cache.put(cache_key, verified.clone());
+        }
+        if keep_timings {
+            verified.timings = timings;
+        }
+    }
+    result
+}
+
+/// Verifies a presentation body and, if the caller set `required_min_score`, checks the score against it.
+/// Once every check passed, records the proof as the latest for its subject (if configured).
+fn verify_with_required_score(
+    body: &str,
+    required_min_score: Option<u64>,
+    deadline: &Deadline,
+    keep_timings: bool,
+) -> Result<VerificationResult, VerificationError> {
+    let mut result = verify_presentation_body(body, deadline, keep_timings)?;
+    if let Some(required_min_score) = required_min_score {
+        result = require_min_score(result, required_min_score).map_err(public_verification_error)?;
+    }
+    subject_store::check_and_record(&result).map_err(public_verification_error)?;
+    Ok(result)
+}
+
+/// Runs `verify_with_required_score` on the blocking thread pool, so the CPU-heavy verification
+/// does not stall the async worker serving other requests
+async fn verify_off_worker(
+    body: String,
+    required_min_score: Option<u64>,
+    deadline: Deadline,
+    keep_timings: bool,
+) -> Result<VerificationResult, VerificationError> {
+    web::block(move || verify_with_required_score(&body, required_min_score, &deadline, keep_timings))
+        .await
+        .unwrap_or_else(|e| {
+            Err(VerificationError {
+                code: None,
+                message: format!("Verification task failed: {}", e),
+            })
+        })
+}
+
+/// Generates a signed attestation whose report_data commits to the serialized verification result.
+/// Aborts with `DEADLINE_EXCEEDED` if the request deadline passes first.
+async fn attest_verification(
+    verification_result: &impl Serialize,
+    deadline: &Deadline,
+) -> Result<SignedAttestation, AttestationError> {
+    let verification_str = serde_json::to_string(verification_result).unwrap_or_else(|_| "Failed to serialize verification result".to_string());
+    trace!(verification = %verification_str, "Attesting verification result");
+    let verification_str_hex = hex::encode(verification_str.as_bytes());
+    attest_within_deadline(
+        get_attestation_report_with_signature(
+            &verification_str_hex,
+            "ASCII bytes of the lowercase hex encoding of the JSON-serialized `verification` field",
+        ),
+        deadline,
+    )
+    .await
+}
+
+/// Runs an attestation future, failing with `DEADLINE_EXCEEDED` if the deadline passes first
+async fn attest_within_deadline(
+    attestation: impl std::future::Future<Output = Result<SignedAttestation, AttestationError>>,
+    deadline: &Deadline,
+) -> Result<SignedAttestation, AttestationError> {
+    if deadline.is_expired() {
+        return Err(AttestationError::with_code("DEADLINE_EXCEEDED", "Request deadline exceeded before attestation"));
+    }
+    deadline
+        .run(attestation)
+        .await
+        .unwrap_or_else(|| {
+            Err(AttestationError::with_code("DEADLINE_EXCEEDED", "Request deadline exceeded during attestation"))
+        })
+        .map_err(public_attestation_error)
+}
+
+/// HTTP status used for an error code unless overridden in `TLSN_VERIFIER_ERROR_STATUS_MAP`
+const DEFAULT_ERROR_STATUSES: &[(&str, StatusCode)] = &[
+    ("DEADLINE_EXCEEDED", StatusCode::REQUEST_TIMEOUT),
+    ("QUOTE_QUEUE_FULL", StatusCode::SERVICE_UNAVAILABLE),
+    ("QUOTE_QUEUE_TIMEOUT", StatusCode::SERVICE_UNAVAILABLE),
+    ("QUOTE_WORKER_UNAVAILABLE", StatusCode::SERVICE_UNAVAILABLE),
+    ("BATCH_TOO_LARGE", StatusCode::PAYLOAD_TOO_LARGE),
+    ("CALLBACK_QUEUE_FULL", StatusCode::SERVICE_UNAVAILABLE),
+];
+
+/// Selects the HTTP status for an error: the configured override for its code, then the
+/// built-in default for the code, then `fallback` (also used for errors without a code)
+fn error_status(code: &Option<String>, fallback: StatusCode) -> HttpResponseBuilder {
+    let status = code
+        .as_deref()
+        .and_then(|code| {
+            config::get_error_status_map()
+                .get(code)
+                .and_then(|status| StatusCode::from_u16(*status).ok())
+                .or_else(|| {
+                    DEFAULT_ERROR_STATUSES
+                        .iter()
+                        .find(|(default_code, _)| *default_code == code)
+                        .map(|(_, status)| *status)
+                })
+        })
+        .unwrap_or(fallback);
+    HttpResponse::build(status)
+}
+
+/// Returns whether a verification or attestation error was caused by the request deadline
+fn is_deadline_exceeded(code: &Option<String>) -> bool {
+    code.as_deref() == Some("DEADLINE_EXCEEDED")
+}
+
+/// Returns whether an attestation error was caused by the quote provider being saturated or down
+fn is_quote_queue_unavailable(code: &Option<String>) -> bool {
+    matches!(
+        code.as_deref(),
+        Some("QUOTE_QUEUE_FULL") | Some("QUOTE_QUEUE_TIMEOUT") | Some("QUOTE_WORKER_UNAVAILABLE")
+    )
+}
+
+/// Query parameters accepted by `/verify-proof`
+#[derive(Deserialize, Default)]
+pub struct VerifyProofQuery {
+    #[serde(default)]
+    pub bundle: bool, // Return a single signed attestation bundle instead of the plain response
+    #[serde(default)]
+    pub callback_url: Option<String>, // POST the response here asynchronously and answer 202 immediately
+    #[serde(default)]
+    pub format: Option<OutputFormat>, // Alternative representation of the result
+    #[serde(default)]
+    pub required_min_score: Option<u64>, // Per-request score cutoff, e.g. of the loan product being decided
+    #[serde(default)]
+    pub debug: bool, // Include the attestation's timings and memory usage in the response
+    #[serde(default)]
+    pub envelope: bool, // Wrap the response in a signed, schema-versioned envelope
+}
+
+/// Query parameters accepted by `/attestation`
+#[derive(Deserialize, Default)]
+pub struct AttestationQuery {
+    #[serde(default)]
+    pub envelope: bool, // Wrap the response in a signed, schema-versioned envelope
+}
+
+/// Responds with `value`, wrapped in a signed, schema-versioned envelope if `envelope` is set
+fn respond_enveloped<T: Serialize>(
+    builder: HttpResponseBuilder,
+    format: ResponseFormat,
+    value: &T,
+    envelope: bool,
+) -> HttpResponse {
+    if !envelope {
+        return respond(builder, format, value);
+    }
+    match build_envelope(value) {
+        Ok(envelope) => respond(builder, format, &envelope),
+        Err(e) => respond(HttpResponse::InternalServerError(), format, &public_attestation_error(e)),
+    }
+}
+
+/// Alternative representations of a verification result selected with `?format=`
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Vc, // W3C Verifiable Credential signed by the verifier's did:key
+}
+
+/// Returns the identifier of the API key that authorized `req`, for logging which client called
+fn client_id(req: &HttpRequest) -> String {
+    ApiKeyId::from_request(req).map_or_else(|| "-".to_string(), |id| id.0)
+}
+
+/// Emits the one-line verification summary (if enabled) for a verification started at `started`
+fn summarize_verification(
+    client_id: &str,
+    request_id: Option<&str>,
+    result: &Result<VerificationResult, VerificationError>,
+    started: Instant,
+) {
+    let elapsed_ms = metrics::as_millis_f64(started.elapsed());
+    log_verification_summary(&VerificationSummary::new(result, client_id, request_id, elapsed_ms));
 }
 
 /// Main verification endpoint that handles TLSN proof verification + attestation
 #[post("/verify-proof")]
-pub async fn verify_proof_route(body: String) -> impl Responder {
-    println!("[verify_proof_route] Starting verification route handler");
+pub async fn verify_proof_route(
+    req: HttpRequest,
+    query: web::Query<VerifyProofQuery>,
+    body: String,
+) -> impl Responder {
+    debug!(client_id = %client_id(&req), "Starting verification route handler");
+    let started = Instant::now();
+
+    // Negotiate the response format from the Accept header before doing any work
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+
+    // With a callback URL, verify and attest in the background and deliver the response there
+    if let Some(callback_url) = &query.callback_url {
+        let url = match validate_callback_url(callback_url) {
+            Ok(url) => url,
+            Err(e) => return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e),
+        };
+        // Bound the verifications and deliveries running in the background
+        let slot = match reserve_callback_slot() {
+            Ok(slot) => slot,
+            Err(e) => return respond(error_status(&e.code, StatusCode::SERVICE_UNAVAILABLE), format, &e),
+        };
+        let mut id_bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id_bytes);
+        let callback_id = hex::encode(id_bytes);
+        let response_id = callback_id.clone();
+        let required_min_score = query.required_min_score;
+        let client = client_id(&req);
+        let request_id = access_log::request_id(req.headers()).map(str::to_string);
+        actix_web::rt::spawn(async move {
+            // The client is not waiting, so its latency budget does not apply
+            let deadline = Deadline::default();
+            let verification = verify_off_worker(body, required_min_score, deadline, false).await;
+            summarize_verification(&client, request_id.as_deref(), &verification, started);
+            let attestation = attest_verification(&verification, &deadline).await;
+            deliver_callback(url, callback_id, VerificationResponse { verification, attestation, debug: None }).await;
+            drop(slot);
+        });
+        return respond(
+            HttpResponse::Accepted(),
+            format,
+            &serde_json::json!({ "status": "accepted", "callback_id": response_id }),
+        );
+    }
+
+    // Honor the client's overall latency budget (X-Deadline-Ms) across both steps
+    let deadline = Deadline::from_request(&req);
 
     // Verify the TLSN presentation from the client body
-    let verification_result = verify_proof(&body);
-    let verification_str = serde_json::to_string(&verification_result).unwrap_or_else(|_| "Failed to serialize verification result".to_string());
-    println!("[verify_proof_route] Verification result: {}", verification_str);
-    let verification_str_hex = hex::encode(verification_str.as_bytes());
+    let verification_result = verify_off_worker(body, query.required_min_score, deadline, query.debug).await;
+    summarize_verification(&client_id(&req), access_log::request_id(req.headers()), &verification_result, started);
+
+    // A Verifiable Credential is secured by its own proof, so no quote is requested
+    if query.format == Some(OutputFormat::Vc) {
+        return match verification_result.map(|result| issue_credential(&result)) {
+            Ok(Ok(credential)) => HttpResponse::Ok().content_type(VC_CONTENT_TYPE).body(credential.to_string()),
+            Ok(Err(e)) => respond(HttpResponse::InternalServerError(), format, &public_attestation_error(e)),
+            Err(e) if is_deadline_exceeded(&e.code) => respond(error_status(&e.code, StatusCode::REQUEST_TIMEOUT), format, &e),
+            Err(e) => respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e),
+        };
+    }
     // Generate an attestation quote with signature and key info
-    let attestation = get_attestation_report_with_signature(&verification_str_hex).await;
-    println!("[verify_proof_route] Attestation report generated successfully");
+    let attestation = attest_verification(&verification_result, &deadline).await;
+    debug!(ok = attestation.is_ok(), "Attestation report generated");
     // Combine both into a structured response object
+    let debug = match &attestation {
+        Ok(report) if query.debug => report.metrics.clone(),
+        _ => None,
+    };
     let response = match attestation {
         Ok(report) => {
             VerificationResponse {
                 verification: verification_result,
                 attestation: Ok(report),
+                debug,
             }
         }
         Err(e) => {
             VerificationResponse {
                 verification: verification_result,
                 attestation: Err(e),
+                debug,
             }
         }
     };
 
-    // Determine HTTP response code based on success/failure cases
-    match (&response.verification, &response.attestation) {
-        (Ok(_), Ok(_)) => HttpResponse::Ok().json(&response),                     // All good
-        (Err(_), Ok(_)) => HttpResponse::BadRequest().json(&response),           // Proof invalid
-        (_, Err(_)) => HttpResponse::InternalServerError().json(&response),      // Attestation failure
+    // Determine HTTP response code based on success/failure cases (error codes may be remapped)
+    let status = match (&response.verification, &response.attestation) {
+        (Ok(_), Ok(_)) => HttpResponse::Ok(),                                             // All good
+        (Err(e), _) if is_deadline_exceeded(&e.code) => error_status(&e.code, StatusCode::REQUEST_TIMEOUT),
+        (Err(e), Ok(_)) => error_status(&e.code, StatusCode::BAD_REQUEST),                // Proof invalid
+        (_, Err(e)) => error_status(&e.code, StatusCode::INTERNAL_SERVER_ERROR),          // Attestation failure
+    };
+
+    // Optionally return everything as a single signed bundle
+    if query.bundle {
+        if let VerificationResponse { verification, attestation: Ok(attestation), .. } = response {
+            return match build_attestation_bundle(verification, attestation) {
+                Ok(bundle) => respond_enveloped(status, format, &bundle, query.envelope),
+                Err(e) => respond(HttpResponse::InternalServerError(), format, &public_attestation_error(e)),
+            };
+        }
+    }
+    respond_enveloped(status, format, &response, query.envelope)
+}
+
+/// Verification-only endpoint: verifies a TLSN presentation without requesting a quote, for
+/// environments without a TEE (e.g. local development). Returns the bare `VerificationResult`
+/// with `200`, or the `VerificationError` with `400` (or its mapped status)
+#[post("/verify-proof-only")]
+pub async fn verify_proof_only_route(req: HttpRequest, body: String) -> impl Responder {
+    debug!(client_id = %client_id(&req), "Starting verification-only route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+
+    let deadline = Deadline::from_request(&req);
+    let started = Instant::now();
+    let verification_result = verify_presentation_body(&body, &deadline, false);
+    summarize_verification(&client_id(&req), access_log::request_id(req.headers()), &verification_result, started);
+    match verification_result {
+        Ok(result) => respond(HttpResponse::Ok(), format, &result),
+        Err(e) if is_deadline_exceeded(&e.code) => respond(error_status(&e.code, StatusCode::REQUEST_TIMEOUT), format, &e),
+        Err(e) => respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e),
+    }
+}
+
+/// Correlation endpoint: verifies presentations from different servers (e.g. a bureau's score and a
+/// bank's income) and checks they are about the same subject, then attests the combined result
+#[post("/verify-proof/correlate")]
+pub async fn correlate_route(req: HttpRequest, body: web::Json<CorrelateRequest>) -> impl Responder {
+    debug!("Starting correlation route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+
+    let max_presentations = config::get_max_batch_size();
+    if body.presentations.len() < 2 || body.presentations.len() > max_presentations {
+        let e = VerificationError::with_code(
+            "INVALID_CORRELATION",
+            format!(
+                "Correlation needs between 2 and {} presentations, got {}",
+                max_presentations,
+                body.presentations.len()
+            ),
+        );
+        return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e);
+    }
+
+    let deadline = Deadline::from_request(&req);
+    let verification = correlate_presentations(&body, &deadline);
+    let attestation = attest_verification(&verification, &deadline).await;
+    let response = CorrelationResponse { verification, attestation };
+
+    let status = match (&response.verification, &response.attestation) {
+        (Ok(_), Ok(_)) => HttpResponse::Ok(),
+        (Err(e), _) if is_deadline_exceeded(&e.code) => error_status(&e.code, StatusCode::REQUEST_TIMEOUT),
+        (Err(e), Ok(_)) => error_status(&e.code, StatusCode::BAD_REQUEST),
+        (_, Err(e)) => error_status(&e.code, StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    respond(status, format, &response)
+}
+
+/// Verifies every presentation and reads its subject field (the per-server override, if any,
+/// otherwise `subject_field`). Fails on the first invalid presentation, with `SUBJECT_MISSING`
+/// if a response lacks the field, or with `SUBJECT_MISMATCH` if the subjects differ.
+fn correlate_presentations(
+    request: &CorrelateRequest,
+    deadline: &Deadline,
+) -> Result<CorrelationResult, VerificationError> {
+    let mut subject: Option<String> = None;
+    let mut results = Vec::with_capacity(request.presentations.len());
+    for (index, presentation) in request.presentations.iter().enumerate() {
+        let result = verify_presentation_body(&presentation.to_string(), deadline, false).map_err(|e| VerificationError {
+            code: e.code,
+            message: format!("Presentation {}: {}", index, e.message),
+        })?;
+
+        let field = request.subject_fields.get(&result.server_name).unwrap_or(&request.subject_field);
+        let value = response_field(&result, field).ok_or_else(|| {
+            public_verification_error(VerificationError::with_code(
+                "SUBJECT_MISSING",
+                format!("Presentation {} ({}) has no subject at '{}'", index, result.server_name, field),
+            ))
+        })?;
+        match &subject {
+            None => subject = Some(value),
+            Some(expected) if *expected != value => {
+                return Err(public_verification_error(VerificationError::with_code(
+                    "SUBJECT_MISMATCH",
+                    format!(
+                        "Presentation {} ({}) is about '{}', expected '{}'",
+                        index, result.server_name, value, expected
+                    ),
+                )));
+            }
+            Some(_) => {}
+        }
+        results.push(result);
     }
+    Ok(CorrelationResult { subject: subject.expect("at least two presentations"), results })
+}
+
+/// Query parameters accepted by `POST /verify-proof/batch`
+#[derive(Deserialize, Default)]
+pub struct BatchQuery {
+    #[serde(default, rename = "async")]
+    pub run_async: bool, // Answer 202 with a batch id right away and verify in the background
+}
+
+/// Batch verification endpoint: verifies a JSON array of presentations and attests the results.
+/// In `merkle` mode a single quote covers the Merkle root of all result digests and each item
+/// carries an inclusion proof; in `per_result` mode every item gets its own attestation.
+/// Results are stored by batch id so they can be paged through later.
+/// Also served as `/verify-proofs-batch`.
+#[routes]
+#[post("/verify-proof/batch")]
+#[post("/verify-proofs-batch")]
+pub async fn verify_proof_batch_route(
+    req: HttpRequest,
+    query: web::Query<BatchQuery>,
+    body: String,
+) -> impl Responder {
+    debug!(client_id = %client_id(&req), "Starting batch verification route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+
+    // Parse the batch into individual presentation bodies
+    let presentations: Vec<serde_json::Value> = match serde_json::from_str(&body) {
+        Ok(presentations) => presentations,
+        Err(e) => {
+            let e = VerificationError::with_code(
+                "INVALID_BATCH",
+                format!("Batch body must be a JSON array of presentations: {}", e),
+            );
+            return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &public_verification_error(e));
+        }
+    };
+    if presentations.is_empty() {
+        let e = VerificationError::with_code("INVALID_BATCH", "Batch is empty");
+        return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &public_verification_error(e));
+    }
+    let max_batch_size = config::get_max_batch_size();
+    if presentations.len() > max_batch_size {
+        let e = VerificationError::with_code(
+            "BATCH_TOO_LARGE",
+            format!("Batch has {} presentations, at most {} allowed", presentations.len(), max_batch_size),
+        );
+        return respond(error_status(&e.code, StatusCode::PAYLOAD_TOO_LARGE), format, &public_verification_error(e));
+    }
+
+    let batch_id = batch_store::new_batch_id();
+
+    // Asynchronous batches are verified in the background and fetched later by id
+    if query.run_async {
+        batch_store::store_batch(&batch_id, BatchState::Pending);
+        let background_id = batch_id.clone();
+        actix_web::rt::spawn(async move {
+            // The client is not waiting, so its latency budget does not apply
+            let response = run_batch(background_id.clone(), &presentations, &Deadline::default()).await;
+            batch_store::store_batch(&background_id, BatchState::Complete(Arc::new(response)));
+        });
+        return respond(
+            HttpResponse::Accepted(),
+            format,
+            &serde_json::json!({ "batch_id": batch_id, "status": BatchStatus::Pending }),
+        );
+    }
+
+    let deadline = Deadline::from_request(&req);
+    let response = run_batch(batch_id.clone(), &presentations, &deadline).await;
+    let status = batch_response_status(&response);
+    let body = respond(status, format, &response);
+    batch_store::store_batch(&batch_id, BatchState::Complete(Arc::new(response)));
+    body
+}
+
+/// Verifies every presentation of a batch and attests the results in the configured mode
+async fn run_batch(
+    batch_id: String,
+    presentations: &[serde_json::Value],
+    deadline: &Deadline,
+) -> BatchVerificationResponse {
+    // Verify on the blocking pool, at most `TLSN_VERIFIER_BATCH_CONCURRENCY` at a time, keeping order
+    let deadline_copy = *deadline;
+    let results: Vec<Result<VerificationResult, VerificationError>> = stream::iter(presentations)
+        .map(|presentation| {
+            // Items may be presentation objects or JSON strings holding one
+            let body = match presentation {
+                serde_json::Value::String(body) => body.clone(),
+                other => other.to_string(),
+            };
+            tokio::task::spawn_blocking(move || verify_presentation_body(&body, &deadline_copy, false))
+        })
+        .buffered(config::get_batch_concurrency())
+        .map(|joined| {
+            joined.unwrap_or_else(|e| {
+                Err(VerificationError {
+                    code: None,
+                    message: format!("Verification task failed: {}", e),
+                })
+            })
+        })
+        .collect()
+        .await;
+
+    match config::get_batch_attestation_mode() {
+        BatchAttestationMode::Merkle => {
+            // Hash every serialized result into a leaf and attest only the root
+            let (root, items) = merkle_batch_items(results);
+            let root_hex = hex::encode(root);
+            let attestation = attest_within_deadline(
+                get_attestation_report_with_signature(
+                    &root_hex,
+                    "ASCII bytes of the lowercase hex Merkle root over SHA-256(0x00 || JSON-serialized `verification`) leaves",
+                ),
+                deadline,
+            )
+            .await;
+            BatchVerificationResponse {
+                batch_id: Some(batch_id),
+                mode: BatchAttestationMode::Merkle,
+                merkle_root_hex_encoded: Some(root_hex),
+                attestation: Some(attestation),
+                items,
+            }
+        }
+        BatchAttestationMode::PerResult => {
+            let mut items = Vec::with_capacity(results.len());
+            for verification in results {
+                let attestation = attest_verification(&verification, deadline).await;
+                items.push(BatchItem {
+                    verification,
+                    leaf_hex_encoded: None,
+                    inclusion_proof: None,
+                    attestation: Some(attestation),
+                });
+            }
+            BatchVerificationResponse {
+                batch_id: Some(batch_id),
+                mode: BatchAttestationMode::PerResult,
+                merkle_root_hex_encoded: None,
+                attestation: None,
+                items,
+            }
+        }
+    }
+}
+
+/// Hashes each serialized result of a non-empty batch into a Merkle leaf. Returns the root and
+/// the batch items, each carrying its leaf and inclusion proof.
+fn merkle_batch_items(results: Vec<Result<VerificationResult, VerificationError>>) -> ([u8; 32], Vec<BatchItem>) {
+    let leaves: Vec<[u8; 32]> = results
+        .iter()
+        .map(|result| merkle::hash_leaf(serde_json::to_string(result).unwrap_or_default().as_bytes()))
+        .collect();
+    let root = merkle::root(&leaves).expect("batch is not empty");
+    let items = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, verification)| BatchItem {
+            verification,
+            leaf_hex_encoded: Some(hex::encode(leaves[index])),
+            inclusion_proof: merkle::proof(&leaves, index),
+            attestation: None,
+        })
+        .collect();
+    (root, items)
+}
+
+/// Any attestation failure makes the whole batch fail; running out of time is a 408
+fn batch_response_status(response: &BatchVerificationResponse) -> HttpResponseBuilder {
+    let attestation_errors: Vec<&AttestationError> = response
+        .attestation
+        .iter()
+        .chain(response.items.iter().filter_map(|item| item.attestation.as_ref()))
+        .filter_map(|attestation| attestation.as_ref().err())
+        .collect();
+    let decisive_error = attestation_errors
+        .iter()
+        .find(|e| is_deadline_exceeded(&e.code))
+        .or_else(|| attestation_errors.iter().find(|e| is_quote_queue_unavailable(&e.code)))
+        .or_else(|| attestation_errors.first());
+    match decisive_error {
+        Some(e) => error_status(&e.code, StatusCode::INTERNAL_SERVER_ERROR),
+        None => HttpResponse::Ok(),
+    }
+}
+
+/// Default and maximum page size when paging through a stored batch
+const DEFAULT_BATCH_PAGE_LIMIT: usize = 50;
+const MAX_BATCH_PAGE_LIMIT: usize = 500;
+
+/// Query parameters accepted by `GET /verify-proof/batch/{batch_id}`
+#[derive(Deserialize, Default)]
+pub struct BatchPageQuery {
+    #[serde(default)]
+    pub offset: usize,         // Index of the first item to return
+    pub limit: Option<usize>,  // Number of items to return (default 50, at most 500)
+}
+
+/// Returns one page of a stored batch's results (or its pending status for async batches)
+#[get("/verify-proof/batch/{batch_id}")]
+pub async fn batch_page_route(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<BatchPageQuery>,
+) -> impl Responder {
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+    let batch_id = path.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_BATCH_PAGE_LIMIT).clamp(1, MAX_BATCH_PAGE_LIMIT);
+
+    let page = match batch_store::get_batch(&batch_id) {
+        None => {
+            let e = VerificationError::with_code(
+                "UNKNOWN_BATCH",
+                format!("Batch '{}' does not exist or has expired", batch_id),
+            );
+            return respond(error_status(&e.code, StatusCode::NOT_FOUND), format, &public_verification_error(e));
+        }
+        Some(BatchState::Pending) => BatchPageResponse {
+            batch_id,
+            status: BatchStatus::Pending,
+            total: 0,
+            offset: query.offset,
+            limit,
+            mode: None,
+            merkle_root_hex_encoded: None,
+            attestation: None,
+            items: Vec::new(),
+        },
+        Some(BatchState::Complete(response)) => BatchPageResponse {
+            batch_id,
+            status: BatchStatus::Complete,
+            total: response.items.len(),
+            offset: query.offset,
+            limit,
+            mode: Some(response.mode),
+            merkle_root_hex_encoded: response.merkle_root_hex_encoded.clone(),
+            attestation: response.attestation.clone(),
+            items: response.items.iter().skip(query.offset).take(limit).cloned().collect(),
+        },
+    };
+    respond(HttpResponse::Ok(), format, &page)
+}
+
+/// Returns the Merkle inclusion proof of a single result of a stored `merkle`-mode batch,
+/// together with the attested root, so a client interested in one result can skip the rest
+#[get("/batch/{batch_id}/inclusion/{index}")]
+pub async fn batch_inclusion_route(req: HttpRequest, path: web::Path<(String, usize)>) -> impl Responder {
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+    let (batch_id, index) = path.into_inner();
+
+    let response = match batch_store::get_batch(&batch_id) {
+        None => {
+            let e = VerificationError::with_code(
+                "UNKNOWN_BATCH",
+                format!("Batch '{}' does not exist or has expired", batch_id),
+            );
+            return respond(error_status(&e.code, StatusCode::NOT_FOUND), format, &public_verification_error(e));
+        }
+        Some(BatchState::Pending) => {
+            return respond(
+                HttpResponse::Accepted(),
+                format,
+                &serde_json::json!({ "batch_id": batch_id, "status": BatchStatus::Pending }),
+            );
+        }
+        Some(BatchState::Complete(response)) => response,
+    };
+
+    let (Some(root_hex), Some(attestation)) = (&response.merkle_root_hex_encoded, &response.attestation) else {
+        let e = VerificationError::with_code(
+            "NOT_MERKLE_BATCH",
+            format!("Batch '{}' was attested per result and has no inclusion proofs", batch_id),
+        );
+        return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &public_verification_error(e));
+    };
+    let Some(BatchItem { verification, leaf_hex_encoded: Some(leaf), inclusion_proof: Some(proof), .. }) =
+        response.items.get(index)
+    else {
+        let e = VerificationError::with_code(
+            "UNKNOWN_BATCH_ITEM",
+            format!("Batch '{}' has {} items, no item {}", batch_id, response.items.len(), index),
+        );
+        return respond(error_status(&e.code, StatusCode::NOT_FOUND), format, &public_verification_error(e));
+    };
+
+    respond(
+        HttpResponse::Ok(),
+        format,
+        &InclusionProofResponse {
+            batch_id,
+            index,
+            total: response.items.len(),
+            merkle_root_hex_encoded: root_hex.clone(),
+            attestation: attestation.clone(),
+            verification: verification.clone(),
+            leaf_hex_encoded: leaf.clone(),
+            inclusion_proof: proof.clone(),
+        },
+    )
 }
 
 /// Standalone attestation endpoint that returns only the attestation data
 #[get("/attestation")]
-pub async fn attestation_route() -> impl Responder {
-    println!("[attestation] Starting attestation route handler");
+pub async fn attestation_route(req: HttpRequest, query: web::Query<AttestationQuery>) -> impl Responder {
+    debug!("Starting attestation route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
 
     // Generate and return attestation report with signature
-    let attestation = get_attestation_report_with_signature("").await;
+    let attestation = get_attestation_report_with_signature("", "empty string").await;
     match attestation {
-        Ok(report) => HttpResponse::Ok().json(report),               // Success
-        Err(e) => HttpResponse::InternalServerError().json(e),       // Failure
+        Ok(report) => respond_enveloped(HttpResponse::Ok(), format, &report, query.envelope), // Success
+        Err(e) => {
+            let e = public_attestation_error(e);
+            let status = error_status(&e.code, StatusCode::INTERNAL_SERVER_ERROR);
+            respond_enveloped(status, format, &e, query.envelope) // Failure
+        }
+    }
+}
+
+/// Byte lengths accepted for a client-provided digest (SHA-256/Keccak-256 and SHA-512)
+const ACCEPTED_DIGEST_LENGTHS: [usize; 2] = [32, 64];
+
+/// Normalizes a client-provided digest to lowercase hex without `0x`.
+/// Fails with `INVALID_DIGEST` if it is not hex or not an accepted length.
+fn normalize_digest_hex(digest_hex: &str) -> Result<String, AttestationError> {
+    let digest = digest_hex.trim();
+    let digest = digest.strip_prefix("0x").unwrap_or(digest);
+    let bytes = hex::decode(digest)
+        .map_err(|e| AttestationError::with_code("INVALID_DIGEST", format!("digest_hex is not valid hex: {}", e)))?;
+    if !ACCEPTED_DIGEST_LENGTHS.contains(&bytes.len()) {
+        return Err(AttestationError::with_code(
+            "INVALID_DIGEST",
+            format!("digest_hex must be 32 or 64 bytes, got {}", bytes.len()),
+        ));
+    }
+    Ok(hex::encode(bytes))
+}
+
+/// Attestation-only endpoint: skips verification and attests over a digest the client provides,
+/// e.g. of a proof it already verified elsewhere
+#[post("/attest")]
+pub async fn attest_digest_route(req: HttpRequest, body: web::Json<AttestDigestRequest>) -> impl Responder {
+    debug!("Starting attest-digest route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+
+    let digest_hex = match normalize_digest_hex(&body.digest_hex) {
+        Ok(digest_hex) => digest_hex,
+        Err(e) => return respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e),
+    };
+
+    // The digest is the report_data input, so the quote commits to exactly what the client asked for
+    let deadline = Deadline::from_request(&req);
+    let attestation = attest_within_deadline(
+        get_attestation_report_with_signature(
+            &digest_hex,
+            "ASCII bytes of the lowercase hex encoding (without `0x`) of the request's `digest_hex`",
+        ),
+        &deadline,
+    )
+    .await;
+    match attestation {
+        Ok(report) => respond(HttpResponse::Ok(), format, &report),
+        Err(e) => respond(error_status(&e.code, StatusCode::INTERNAL_SERVER_ERROR), format, &e),
+    }
+}
+
+/// Admin endpoint re-reading the per-server policy file without a restart.
+/// Returns the applied policies and clears the result cache, or the validation error with the running
+/// policies unchanged.
+#[post("/admin/reload-policies")]
+pub async fn reload_policies_route(req: HttpRequest) -> impl Responder {
+    debug!("Starting reload-policies route handler");
+
+    let format = match ResponseFormat::from_request(&req) {
+        Some(format) => format,
+        None => return not_acceptable(),
+    };
+    match server_policy::reload_server_policies() {
+        Ok(mut summary) => {
+            // Cached results were checked against the old policies; drop them so they are re-checked
+            summary.result_cache_evicted = try_get_result_cache().map_or(0, |c| c.clear());
+            info!(result_cache_evicted = summary.result_cache_evicted, "Evicted cached results after policy reload");
+            respond(HttpResponse::Ok(), format, &summary)
+        }
+        Err(e) => {
+            // Admins need the detail (e.g. which pattern is invalid), so the message is not redacted
+            warn!(error = %e, "Policy reload failed");
+            let e = VerificationError::with_code("INVALID_POLICY", format!("Policy reload failed: {}", e));
+            respond(error_status(&e.code, StatusCode::BAD_REQUEST), format, &e)
+        }
+    }
+}
+
+/// Admin endpoint evicting cached data so the next request is verified (and attested) afresh.
+/// `target` is a presentation hash, evicting that presentation's cached result, or `all`,
+/// clearing the result and quote caches. Returns how many entries were evicted.
+#[post("/admin/cache/invalidate")]
+pub async fn invalidate_cache_route(body: web::Json<InvalidateCacheRequest>) -> impl Responder {
+    debug!("Starting cache invalidation route handler");
+
+    let cache = try_get_result_cache();
+    let target = body.target.trim().to_lowercase();
+    let (result_cache_evicted, quote_cache_evicted) = if target == "all" {
+        (cache.map_or(0, |c| c.clear()), clear_quote_cache())
+    } else {
+        let hash = target.strip_prefix("0x").unwrap_or(&target);
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return HttpResponse::BadRequest()
+                .body("target must be `all` or a hex-encoded SHA-256 presentation hash");
+        }
+        (cache.map_or(0, |c| c.invalidate(hash)), 0)
+    };
+    info!(result_cache_evicted, quote_cache_evicted, "Evicted cached data");
+    HttpResponse::Ok().json(InvalidateCacheResponse {
+        result_cache_evicted,
+        quote_cache_evicted,
+        evicted: result_cache_evicted + quote_cache_evicted,
+    })
+}
+
+/// Re-derives the signing key (from tappd, or a new random key) and swaps it in without a restart.
+/// Cached quotes are evicted, since they were signed for the previous key.
+#[post("/rotate-key")]
+pub async fn rotate_key_route() -> impl Responder {
+    debug!("Starting rotate-key route handler");
+
+    let (previous, key_material) = match rotate_key_material().await {
+        Ok(rotated) => rotated,
+        Err(e) => return HttpResponse::InternalServerError().json(e),
+    };
+    let quote_cache_evicted = clear_quote_cache();
+    let verifying_key_hex_encoded = key_material.encode_verify_key();
+    let previous_verifying_key_hex_encoded = previous.map(|previous| previous.encode_verify_key());
+    if previous_verifying_key_hex_encoded.as_deref() == Some(verifying_key_hex_encoded.as_str()) {
+        warn!("The re-derived key equals the previous key");
+    }
+    HttpResponse::Ok().json(RotateKeyResponse {
+        verifying_key_hex_encoded,
+        previous_verifying_key_hex_encoded,
+        key_source: key_material.source.clone(),
+        quote_cache_evicted,
+    })
+}
+
+/// Admin endpoint comparing another verifier's public key with this instance's key,
+/// used to detect misprovisioned enclaves in a cluster
+#[post("/admin/compare-key")]
+pub async fn compare_key_route(body: web::Json<CompareKeyRequest>) -> impl Responder {
+    debug!("Starting compare-key route handler");
+
+    let key_material = match try_get_key_material() {
+        Some(km) => km,
+        None => return HttpResponse::InternalServerError().body("Key material not initialized"),
+    };
+
+    // Decode the remote key so compressed and uncompressed encodings compare equal
+    let remote_key = match key_material.verifying_key_from_hex_encoded(&body.verifying_key_hex_encoded) {
+        Ok(key) => key,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid verifying key: {}", e)),
+    };
+
+    let response = CompareKeyResponse {
+        matches: remote_key == key_material.verifying_key(),
+        local_key_source: key_material.source.clone(),
+        remote_key_source: body.key_source.clone(),
+    };
+    HttpResponse::Ok().json(response)
+}
+
+/// Stores a base presentation server-side so later delta proofs can reference it via `session_ref`
+#[post("/sessions")]
+pub async fn store_session_route(body: String) -> impl Responder {
+    debug!("Starting store-session route handler");
+
+    match store_base_session(&body) {
+        Ok((session_id, expires_at)) => HttpResponse::Ok().json(SessionResponse { session_id, expires_at }),
+        Err(e) => {
+            let e = public_verification_error(e);
+            error_status(&e.code, StatusCode::BAD_REQUEST).json(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::tests::root_from_proof;
+    use actix_web::{test, App};
+
+    /// Stores a completed `merkle`-mode batch of `count` (failed) results and returns its id
+    fn store_merkle_batch(count: usize) -> String {
+        let results = (0..count)
+            .map(|i| Err(VerificationError::with_code("INVALID_PRESENTATION", format!("Presentation {} is invalid", i))))
+            .collect();
+        let (root, items) = merkle_batch_items(results);
+        let batch_id = batch_store::new_batch_id();
+        let response = BatchVerificationResponse {
+            batch_id: Some(batch_id.clone()),
+            mode: BatchAttestationMode::Merkle,
+            merkle_root_hex_encoded: Some(hex::encode(root)),
+            attestation: Some(Err(AttestationError::with_code("QUOTE_QUEUE_TIMEOUT", "No quote provider in tests"))),
+            items,
+        };
+        batch_store::store_batch(&batch_id, BatchState::Complete(Arc::new(response)));
+        batch_id
+    }
+
+    /// Requests `uri` from the inclusion route and returns the status and JSON body
+    async fn get_inclusion(uri: &str) -> (StatusCode, serde_json::Value) {
+        let app = test::init_service(App::new().service(batch_inclusion_route)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        let status = res.status();
+        (status, test::read_body_json(res).await)
+    }
+
+    #[actix_web::test]
+    async fn inclusion_proofs_lead_to_the_attested_root() {
+        let batch_id = store_merkle_batch(5);
+        for index in 0..5 {
+            let (status, body) = get_inclusion(&format!("/batch/{}/inclusion/{}", batch_id, index)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["index"], index);
+            assert_eq!(body["total"], 5);
+
+            // The leaf is the hash of the returned verification, and its proof leads to the root
+            let verification = serde_json::to_string(&body["verification"]).unwrap();
+            let leaf = merkle::hash_leaf(verification.as_bytes());
+            assert_eq!(body["leaf_hex_encoded"], hex::encode(leaf));
+            let proof: Vec<merkle::ProofStep> = serde_json::from_value(body["inclusion_proof"].clone()).unwrap();
+            assert_eq!(body["merkle_root_hex_encoded"], hex::encode(root_from_proof(&leaf, &proof)));
+        }
+    }
+
+    #[actix_web::test]
+    async fn out_of_range_index_is_an_unknown_batch_item() {
+        let batch_id = store_merkle_batch(3);
+        let (status, body) = get_inclusion(&format!("/batch/{}/inclusion/3", batch_id)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "UNKNOWN_BATCH_ITEM");
+    }
+
+    #[actix_web::test]
+    async fn batch_errors_use_the_negotiated_format() {
+        let app = test::init_service(App::new().service(batch_page_route).service(batch_inclusion_route)).await;
+        for uri in ["/verify-proof/batch/unknown", "/batch/unknown/inclusion/0"] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT, "application/cbor"))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", uri);
+            assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "application/cbor", "{}", uri);
+            let body: VerificationError = ciborium::from_reader(&test::read_body(res).await[..]).unwrap();
+            assert_eq!(body.code.as_deref(), Some("UNKNOWN_BATCH"), "{}", uri);
+
+            let req = test::TestRequest::get().uri(uri).insert_header((header::ACCEPT, "text/html")).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_ACCEPTABLE, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn unknown_and_expired_batches_are_not_found() {
+        let (status, body) = get_inclusion(&format!("/batch/{}/inclusion/0", batch_store::new_batch_id())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "UNKNOWN_BATCH");
+
+        let expired_id = batch_store::new_batch_id();
+        batch_store::tests::store_expired_batch(&expired_id, BatchState::Pending);
+        let (status, body) = get_inclusion(&format!("/batch/{}/inclusion/0", expired_id)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "UNKNOWN_BATCH");
     }
 }
@@ -0,0 +1,182 @@
+use crate::quote;
+use crate::types::{SignedAttestation, SigningMode, VerificationResponse};
+use crate::utils;
+use p256::ecdsa::signature::Verifier;
+use sha3::{Digest, Keccak256};
+use std::fs;
+
+/// Outcome of one offline check
+enum CheckOutcome {
+    Pass,
+    Fail(String),
+    Skipped(String),
+}
+
+/// Re-runs the signature and attestation checks on a saved `VerificationResponse` JSON file
+/// without the original presentation, prints a pass/fail report and returns whether all
+/// performed checks passed
+pub fn verify_saved_response(path: &str) -> bool {
+    println!("Verifying saved response {}", path);
+    let response: VerificationResponse = match fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))
+        .and_then(|content| {
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path, e))
+        }) {
+        Ok(response) => response,
+        Err(e) => {
+            println!("FAIL  {}", e);
+            return false;
+        }
+    };
+    let attestation = match &response.attestation {
+        Ok(attestation) => attestation,
+        Err(e) => {
+            println!("FAIL  Response carries no attestation: {}", e.message);
+            return false;
+        }
+    };
+
+    let checks = [
+        ("quote signature", check_quote_signature(attestation)),
+        ("operator signature", check_operator_signature(attestation)),
+        ("report_data binds verification", check_report_data_binding(&response, attestation)),
+        (
+            "quote signature chain (DCAP)",
+            CheckOutcome::Skipped("no offline TDX collateral verifier is built in".to_string()),
+        ),
+    ];
+    let mut all_passed = true;
+    for (name, outcome) in checks {
+        match outcome {
+            CheckOutcome::Pass => println!("PASS  {}", name),
+            CheckOutcome::Fail(reason) => {
+                all_passed = false;
+                println!("FAIL  {}: {}", name, reason);
+            }
+            CheckOutcome::Skipped(reason) => println!("SKIP  {}: {}", name, reason),
+        }
+    }
+    println!("{}", if all_passed { "Result: PASS" } else { "Result: FAIL" });
+    all_passed
+}
+
+/// Checks the signature over the hex-encoded quote against the embedded verifying key,
+/// using the scheme recorded in `signing_mode`
+fn check_quote_signature(attestation: &SignedAttestation) -> CheckOutcome {
+    if let Some(scheme) = attestation.scheme {
+        let signing_mode = attestation.signing_mode;
+        if signing_mode != SigningMode::Keccak256Recoverable && signing_mode != SigningMode::for_scheme(scheme) {
+            return CheckOutcome::Fail(format!(
+                "scheme {:?} does not match signing_mode {:?}",
+                scheme, signing_mode
+            ));
+        }
+    }
+    if let Some(algorithm) = attestation.algorithm {
+        if algorithm != attestation.signing_mode.algorithm() {
+            return CheckOutcome::Fail(format!(
+                "algorithm {:?} does not match signing_mode {:?}",
+                algorithm, attestation.signing_mode
+            ));
+        }
+    }
+    let message = utils::encode_message_hex(&attestation.quote);
+    let result = match attestation.signing_mode {
+        SigningMode::P256Sha256 => verify_p256(attestation, message.as_bytes()),
+        SigningMode::Keccak256Recoverable => verify_keccak256(attestation, message.as_bytes()),
+        SigningMode::Ed25519 => verify_ed25519(attestation, message.as_bytes()),
+    };
+    match result {
+        Ok(()) => CheckOutcome::Pass,
+        Err(e) => CheckOutcome::Fail(e),
+    }
+}
+
+/// Checks the operator's P-256 countersignature over the hex-encoded quote, if present
+fn check_operator_signature(attestation: &SignedAttestation) -> CheckOutcome {
+    let (Some(signature_hex), Some(key_hex)) =
+        (&attestation.operator_signature_hex, &attestation.operator_verifying_key_hex)
+    else {
+        return CheckOutcome::Skipped("response carries no operator signature".to_string());
+    };
+    let message = utils::encode_message_hex(&attestation.quote);
+    match verify_p256_with(key_hex, signature_hex, message.as_bytes()) {
+        Ok(()) => CheckOutcome::Pass,
+        Err(e) => CheckOutcome::Fail(e),
+    }
+}
+
+/// Verifies a P-256 ECDSA/SHA-256 signature
+fn verify_p256(attestation: &SignedAttestation, message: &[u8]) -> Result<(), String> {
+    verify_p256_with(&attestation.verifying_key_hex_encoded, &attestation.signature_hex_encoded, message)
+}
+
+/// Verifies a hex-encoded P-256 ECDSA/SHA-256 signature against a hex-encoded SEC1 key
+fn verify_p256_with(key_hex: &str, signature_hex: &str, message: &[u8]) -> Result<(), String> {
+    let key_bytes = hex::decode(key_hex).map_err(|e| e.to_string())?;
+    let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&key_bytes).map_err(|e| e.to_string())?;
+    let sig_bytes = hex::decode(signature_hex).map_err(|e| e.to_string())?;
+    let signature = p256::ecdsa::Signature::from_slice(&sig_bytes).map_err(|e| e.to_string())?;
+    key.verify(message, &signature).map_err(|e| e.to_string())
+}
+
+/// Verifies an Ed25519 signature against the embedded 32-byte public key
+fn verify_ed25519(attestation: &SignedAttestation, message: &[u8]) -> Result<(), String> {
+    let key_bytes = hex::decode(&attestation.verifying_key_hex_encoded).map_err(|e| e.to_string())?;
+    let sig_bytes = hex::decode(&attestation.signature_hex_encoded).map_err(|e| e.to_string())?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, &key_bytes)
+        .verify(message, &sig_bytes)
+        .map_err(|_| "Ed25519 signature does not match the embedded key".to_string())
+}
+
+/// Recovers the signer of a 65-byte `r || s || v` keccak256 signature and compares it to the
+/// embedded secp256k1 key
+fn verify_keccak256(attestation: &SignedAttestation, message: &[u8]) -> Result<(), String> {
+    let sig_bytes = hex::decode(&attestation.signature_hex_encoded).map_err(|e| e.to_string())?;
+    let (rs, v) = match sig_bytes.as_slice() {
+        [rs @ .., v] if rs.len() == 64 => (rs, *v),
+        _ => return Err(format!("Expected a 65-byte signature, got {} bytes", sig_bytes.len())),
+    };
+    let signature = k256::ecdsa::Signature::from_slice(rs).map_err(|e| e.to_string())?;
+    let recovery_id = k256::ecdsa::RecoveryId::from_byte(v.wrapping_sub(27))
+        .ok_or_else(|| format!("Invalid recovery byte {}", v))?;
+    let recovered =
+        k256::ecdsa::VerifyingKey::recover_from_prehash(&Keccak256::digest(message), &signature, recovery_id)
+            .map_err(|e| e.to_string())?;
+    let recovered_hex = hex::encode(recovered.to_encoded_point(false).as_bytes());
+    if recovered_hex != attestation.verifying_key_hex_encoded.to_lowercase() {
+        return Err("Recovered signer does not match the verifying key".to_string());
+    }
+    Ok(())
+}
+
+/// Recomputes report_data from the serialized `verification` field and compares it with the
+/// report_data embedded in the quote (shorter digests are zero-padded to 64 bytes)
+fn check_report_data_binding(response: &VerificationResponse, attestation: &SignedAttestation) -> CheckOutcome {
+    let embedded = match quote::report_data_from_hex_quote(&attestation.quote) {
+        Ok(report_data) => report_data,
+        Err(e) => return CheckOutcome::Fail(e),
+    };
+    let verification_str = match serde_json::to_string(&response.verification) {
+        Ok(s) => s,
+        Err(e) => return CheckOutcome::Fail(e.to_string()),
+    };
+    let expected_hex = utils::prepare_report_data(
+        &hex::encode(verification_str.as_bytes()),
+        attestation.report_data_hash_algorithm,
+    );
+    let expected = match hex::decode(expected_hex.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(e) => return CheckOutcome::Fail(e.to_string()),
+    };
+    let (digest, padding) = embedded.split_at(expected.len().min(embedded.len()));
+    if digest == expected.as_slice() && padding.iter().all(|b| *b == 0) {
+        CheckOutcome::Pass
+    } else {
+        CheckOutcome::Fail(format!(
+            "Quote report_data {} does not match {}",
+            hex::encode(embedded),
+            hex::encode(&expected)
+        ))
+    }
+}
@@ -0,0 +1,131 @@
+use crate::config;
+use crate::verifier::normalize_domain;
+use once_cell::sync::OnceCell;
+use std::collections::HashSet;
+use tracing::info;
+
+/// Singleton holding the accepted server names in normalized (IDNA ASCII) form
+static ACCEPTED_SERVER_NAMES: OnceCell<HashSet<String>> = OnceCell::new();
+
+/// Maximum length of a hostname in ASCII form (RFC 1035)
+const MAX_HOSTNAME_LEN: usize = 253;
+
+/// Maximum length of a single hostname label (RFC 1035)
+const MAX_LABEL_LEN: usize = 63;
+
+/// Checks that `name` (in ASCII form) is a syntactically valid hostname:
+/// dot-separated labels of letters, digits and hyphens, not starting or ending with a hyphen
+fn validate_hostname(name: &str) -> Result<(), String> {
+    if name.len() > MAX_HOSTNAME_LEN {
+        return Err(format!("is longer than {} characters", MAX_HOSTNAME_LEN));
+    }
+    for label in name.strip_suffix('.').unwrap_or(name).split('.') {
+        if label.is_empty() || label.len() > MAX_LABEL_LEN {
+            return Err(format!("has a label that is empty or longer than {} characters", MAX_LABEL_LEN));
+        }
+        if !label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+            return Err(format!("has an invalid character in label '{}'", label));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("has label '{}' starting or ending with '-'", label));
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes and validates the configured server names.
+/// Fails if there are more than the configured maximum or any entry is not a valid hostname.
+fn load_from_config() -> Result<HashSet<String>, String> {
+    let names = config::get_server_names();
+    let max_names = config::get_max_server_names();
+    if names.len() > max_names {
+        return Err(format!(
+            "TLSN_VERIFIER_ACCEPTED_SERVER_NAMES has {} entries, more than the maximum of {} \
+             (raise TLSN_VERIFIER_MAX_SERVER_NAMES if this is intended)",
+            names.len(),
+            max_names
+        ));
+    }
+    names
+        .iter()
+        .map(|name| {
+            let normalized = normalize_domain(name);
+            validate_hostname(&normalized)
+                .map(|_| normalized)
+                .map_err(|e| format!("Invalid accepted server name '{}': {}", name, e))
+        })
+        .collect()
+}
+
+/// Loads the accepted server names from `TLSN_VERIFIER_ACCEPTED_SERVER_NAMES`.
+/// Fails if the list is too long or contains an invalid hostname.
+pub fn init_accepted_server_names() -> Result<(), String> {
+    let names = load_from_config()?;
+    info!(count = names.len(), "Loaded accepted server names");
+    ACCEPTED_SERVER_NAMES
+        .set(names)
+        .map_err(|_| "Accepted server names already initialized".to_string())
+}
+
+/// Returns whether `server_name` is in the accepted list (compared in IDNA ASCII form).
+/// Loads the list without validation if it was not initialized at startup.
+pub fn is_accepted(server_name: &str) -> bool {
+    ACCEPTED_SERVER_NAMES
+        .get_or_init(|| config::get_server_names().iter().map(|name| normalize_domain(name)).collect())
+        .contains(&normalize_domain(server_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_names_normalize_to_punycode() {
+        let cases = [
+            ("bücher.example", "xn--bcher-kva.example"),
+            ("BÜCHER.Example", "xn--bcher-kva.example"),
+            ("xn--bcher-kva.example", "xn--bcher-kva.example"),
+            ("XN--BCHER-KVA.EXAMPLE", "xn--bcher-kva.example"),
+            ("münchen.de", "xn--mnchen-3ya.de"),
+            ("例え.テスト", "xn--r8jz45g.xn--zckzah"),
+            ("api.Example.COM", "api.example.com"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(normalize_domain(name), expected, "{}", name);
+            assert!(validate_hostname(&normalize_domain(name)).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn unicode_and_punycode_forms_compare_equal() {
+        assert_eq!(normalize_domain("bücher.example"), normalize_domain("xn--bcher-kva.example"));
+        // Full-width characters are mapped to ASCII by IDNA
+        assert_eq!(normalize_domain("ｅｘａｍｐｌｅ.com"), "example.com");
+        assert_ne!(normalize_domain("bücher.example"), normalize_domain("bucher.example"));
+    }
+
+    #[test]
+    fn invalid_hostnames_are_rejected() {
+        let cases = [
+            "exa_mple.com",
+            "-example.com",
+            "example-.com",
+            "example..com",
+            "",
+            "example.com/path",
+            "user@example.com",
+        ];
+        for name in cases {
+            assert!(validate_hostname(&normalize_domain(name)).is_err(), "{:?}", name);
+        }
+        let long_label = format!("{}.com", "a".repeat(MAX_LABEL_LEN + 1));
+        assert!(validate_hostname(&long_label).is_err());
+        let long_name = vec!["a".repeat(MAX_LABEL_LEN); 4].join(".") + ".com";
+        assert!(validate_hostname(&long_name).is_err());
+    }
+
+    #[test]
+    fn trailing_dot_is_accepted() {
+        assert!(validate_hostname(&normalize_domain("example.com.")).is_ok());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::config;
+use crate::json_path;
+use arc_swap::ArcSwap;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::Arc;
+use tracing::info;
+
+/// Request path accepted for servers without their own `accepted_paths`
+const DEFAULT_ACCEPTED_PATH: &str = "/users/[^/]+/credit-score";
+
+/// `DEFAULT_ACCEPTED_PATH`, compiled once
+static DEFAULT_PATH_REGEX: Lazy<Regex> =
+    Lazy::new(|| anchored_regex(DEFAULT_ACCEPTED_PATH).expect("default accepted path is a valid regex"));
+
+/// Per-server policies keyed by IDNA-normalized server name, swapped as a whole on reload.
+/// Readers never block, not even while a reload stores new policies.
+static SERVER_POLICIES: Lazy<ArcSwap<HashMap<String, ServerPolicy>>> =
+    Lazy::new(|| ArcSwap::from_pointee(HashMap::new()));
+
+/// Verification rules for one server (bureau), loaded from a JSON policy file.
+/// Each field is optional; an absent field falls back to the global default.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ServerPolicy {
+    pub min_score: Option<u64>,                 // Lowest accepted score
+    pub score_pointer: Option<String>,          // JSON pointer or `$`-rooted JSONPath locating the score
+    pub score_encoded_pointer: Option<String>,  // Base64-encoded JSON field the score pointer applies within
+    pub accepted_paths: Option<Vec<String>>,    // Regex patterns the full request path must match
+    pub accepted_versions: Option<Vec<String>>, // TLSN presentation versions accepted from this server
+    pub cert_fingerprints: Option<Vec<String>>, // Pinned SHA-256 fingerprints (hex) of the leaf certificate
+    #[serde(skip)]
+    path_regexes: Vec<Regex>,                   // `accepted_paths`, compiled when the policy is loaded
+}
+
+impl ServerPolicy {
+    /// Loads the policies from a JSON file mapping server names to policies, compiling their path
+    /// patterns. Fails if a path pattern is not a valid regex or a fingerprint is not a SHA-256 hex digest.
+    pub fn load_from_file(path: &str) -> Result<HashMap<String, ServerPolicy>, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let mut policies: HashMap<String, ServerPolicy> =
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+        for (server_name, policy) in &mut policies {
+            if let Some(path) = policy.score_pointer.as_deref().filter(|p| p.starts_with('$')) {
+                json_path::validate(path).map_err(|e| format!("Invalid score path for {}: {}", server_name, e))?;
+            }
+            if let Some(path) = policy.score_encoded_pointer.as_deref().filter(|p| p.starts_with('$')) {
+                json_path::validate(path)
+                    .map_err(|e| format!("Invalid encoded score path for {}: {}", server_name, e))?;
+            }
+            policy.path_regexes = policy
+                .accepted_paths
+                .iter()
+                .flatten()
+                .map(|pattern| {
+                    anchored_regex(pattern)
+                        .map_err(|e| format!("Invalid accepted path '{}' for {}: {}", pattern, server_name, e))
+                })
+                .collect::<Result<_, _>>()?;
+            for fingerprint in policy.cert_fingerprints.iter().flatten() {
+                let bytes = hex::decode(normalize_fingerprint(fingerprint)).unwrap_or_default();
+                if bytes.len() != 32 {
+                    return Err(format!(
+                        "Invalid certificate fingerprint '{}' for {}: expected a SHA-256 hex digest",
+                        fingerprint, server_name
+                    ));
+                }
+            }
+        }
+        Ok(policies
+            .into_iter()
+            .map(|(server_name, policy)| (normalize_server_name(&server_name), policy))
+            .collect())
+    }
+
+    /// Returns whether `request_path` matches one of the accepted path patterns, as compiled
+    /// by `load_from_file` (the default path for servers without their own `accepted_paths`)
+    pub fn accepts_path(&self, request_path: &str) -> bool {
+        match &self.accepted_paths {
+            Some(_) => self.path_regexes.iter().any(|regex| regex.is_match(request_path)),
+            None => DEFAULT_PATH_REGEX.is_match(request_path),
+        }
+    }
+
+    /// Returns whether a leaf certificate fingerprint (lowercase hex SHA-256) is accepted:
+    /// always if no fingerprints are pinned, otherwise only if it is one of them
+    pub fn accepts_cert_fingerprint(&self, fingerprint: Option<&str>) -> bool {
+        match &self.cert_fingerprints {
+            Some(pinned) => fingerprint.is_some_and(|fp| pinned.iter().any(|p| normalize_fingerprint(p) == fp)),
+            None => true,
+        }
+    }
+
+    /// Returns whether presentations of `version` are accepted for this server
+    /// (the default versions for servers without their own `accepted_versions`)
+    pub fn accepts_version(&self, version: &str) -> bool {
+        match &self.accepted_versions {
+            Some(versions) => versions.iter().any(|v| v == version),
+            None => is_default_accepted_version(version),
+        }
+    }
+}
+
+/// Returns whether a version is accepted for servers without their own `accepted_versions`:
+/// it must lie in `TLSN_VERIFIER_VERSION_RANGE` if set (with semver pre-release rules, so
+/// `0.1.0-alpha.11` matches `>=0.1.0-alpha.10` but `0.1.1-alpha.1` does not), otherwise
+/// be one of `TLSN_VERIFIER_ACCEPTED_VERSIONS`
+pub fn is_default_accepted_version(version: &str) -> bool {
+    match config::get_version_range() {
+        Some(range) => version_in_range(version, &range),
+        None => config::get_accepted_versions().iter().any(|v| v == version),
+    }
+}
+
+/// Returns whether `version` parses as semver and lies in `range`
+pub fn version_in_range(version: &str, range: &VersionReq) -> bool {
+    Version::parse(version).is_ok_and(|version| range.matches(&version))
+}
+
+/// Describes the default accepted versions for error messages
+pub fn describe_default_accepted_versions() -> String {
+    match config::get_version_range() {
+        Some(range) => format!("a version in range '{}'", range),
+        None => {
+            let versions: Vec<String> = config::get_accepted_versions()
+                .iter()
+                .map(|v| format!("'{}'", v))
+                .collect();
+            match versions.as_slice() {
+                [version] => version.clone(),
+                _ => format!("one of {}", versions.join(", ")),
+            }
+        }
+    }
+}
+
+/// Returns whether a version is accepted for some server: by default or by any per-server
+/// `accepted_versions`. Used to reject unknown versions before the server is known.
+pub fn is_accepted_for_any_server(version: &str) -> bool {
+    is_default_accepted_version(version)
+        || current_policies()
+            .values()
+            .any(|policy| policy.accepted_versions.iter().flatten().any(|v| v == version))
+}
+
+/// Returns whether any server pins certificate fingerprints
+pub fn has_cert_pins() -> bool {
+    current_policies().values().any(|policy| policy.cert_fingerprints.is_some())
+}
+
+/// Normalizes a fingerprint to lowercase hex, accepting `AA:BB:...` notation
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint.trim().replace(':', "").to_lowercase()
+}
+
+/// Compiles a path pattern so it must match the whole path
+fn anchored_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// Normalizes a server name the way verification compares them (lowercase IDNA ASCII)
+fn normalize_server_name(name: &str) -> String {
+    idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_lowercase())
+}
+
+/// Loads the per-server policies from `TLSN_VERIFIER_SERVER_POLICY_PATH`, if set.
+/// Fails if the file is configured but cannot be read or parsed.
+pub fn init_server_policies() -> Result<(), String> {
+    let Some(path) = config::get_server_policy_path() else {
+        info!("No per-server policy configured");
+        return Ok(());
+    };
+    let policies = ServerPolicy::load_from_file(&path)?;
+    info!(servers = policies.len(), path = %path, "Loaded server policies");
+    SERVER_POLICIES.store(Arc::new(policies));
+    Ok(())
+}
+
+/// Policies applied after a reload, keyed by normalized server name
+#[derive(Debug, Serialize)]
+pub struct PolicySummary {
+    pub path: String,                             // File the policies were read from
+    pub servers: BTreeMap<String, ServerPolicy>,  // Policy of each server
+    pub result_cache_evicted: usize,              // Cached results removed, since they were checked against the old policies
+}
+
+/// Re-reads and validates `TLSN_VERIFIER_SERVER_POLICY_PATH` and swaps the new policies in
+/// atomically. On failure the running policies are left unchanged.
+pub fn reload_server_policies() -> Result<PolicySummary, String> {
+    let path = config::get_server_policy_path().ok_or("TLSN_VERIFIER_SERVER_POLICY_PATH is not set")?;
+    let policies = ServerPolicy::load_from_file(&path)?;
+    let servers = policies.iter().map(|(name, policy)| (name.clone(), policy.clone())).collect();
+    SERVER_POLICIES.store(Arc::new(policies));
+    info!(path = %path, "Reloaded server policies");
+    Ok(PolicySummary { path, servers, result_cache_evicted: 0 })
+}
+
+/// Returns a snapshot of the current policies; a concurrent reload does not affect it
+fn current_policies() -> Arc<HashMap<String, ServerPolicy>> {
+    SERVER_POLICIES.load_full()
+}
+
+/// Returns the effective policy for a verified server name: its own settings where present,
+/// otherwise the global defaults (`TLSN_VERIFIER_MIN_SCORE`, `TLSN_VERIFIER_SCORE_FIELD`
+/// and the default credit-score path)
+pub fn policy_for(server_name: &str) -> ServerPolicy {
+    let own = current_policies()
+        .get(&normalize_server_name(server_name))
+        .cloned()
+        .unwrap_or_default();
+    ServerPolicy {
+        min_score: own.min_score.or_else(config::get_min_score),
+        score_pointer: own.score_pointer.or_else(config::get_score_pointer),
+        score_encoded_pointer: own.score_encoded_pointer.or_else(config::get_score_encoded_field),
+        accepted_paths: own.accepted_paths,
+        accepted_versions: own.accepted_versions,
+        cert_fingerprints: own.cert_fingerprints,
+        path_regexes: own.path_regexes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Policy file in the temp directory, removed when dropped
+    struct TempPolicyFile(std::path::PathBuf);
+
+    impl TempPolicyFile {
+        fn new(name: &str, content: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("tlsn-verifier-policy-{}-{}.json", name, std::process::id()));
+            fs::write(&path, content).unwrap();
+            TempPolicyFile(path)
+        }
+
+        fn load(&self) -> Result<HashMap<String, ServerPolicy>, String> {
+            ServerPolicy::load_from_file(self.0.to_str().unwrap())
+        }
+    }
+
+    impl Drop for TempPolicyFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn accepted_paths_are_compiled_at_load_and_match_whole_paths() {
+        let file = TempPolicyFile::new(
+            "paths",
+            r#"{"API.Bureau-B.com": {"accepted_paths": ["/v2/scores/[^/]+", "/v3/score"]}}"#,
+        );
+        let policies = file.load().unwrap();
+        let policy = &policies["api.bureau-b.com"];
+        assert_eq!(policy.path_regexes.len(), 2);
+        assert!(policy.accepts_path("/v2/scores/alice"));
+        assert!(policy.accepts_path("/v3/score"));
+        assert!(!policy.accepts_path("/v2/scores/alice/extra"));
+        assert!(!policy.accepts_path("/users/alice/credit-score"));
+    }
+
+    #[test]
+    fn default_path_applies_without_accepted_paths() {
+        let policy = ServerPolicy::default();
+        assert!(policy.accepts_path("/users/alice/credit-score"));
+        assert!(!policy.accepts_path("/users/alice/credit-score/history"));
+    }
+
+    #[test]
+    fn invalid_path_pattern_fails_the_load() {
+        let file = TempPolicyFile::new(
+            "invalid",
+            r#"{"api.bureau-a.com": {"accepted_paths": ["/v2/scores/(unclosed"]}}"#,
+        );
+        let error = file.load().unwrap_err();
+        assert!(error.contains("Invalid accepted path '/v2/scores/(unclosed' for api.bureau-a.com"), "{}", error);
+    }
+}
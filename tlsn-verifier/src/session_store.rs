@@ -0,0 +1,103 @@
+use crate::config;
+use crate::types::{PresentationJSON, VerificationError};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// A base presentation uploaded by a client, referenced later by delta proofs
+struct StoredSession {
+    base: Value,     // Base presentation JSON as uploaded
+    expires_at: i64, // Expiry (Unix seconds)
+}
+
+/// Global store of base sessions keyed by session id
+static SESSIONS: Lazy<Mutex<HashMap<String, StoredSession>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Stores a base presentation and returns its session id together with its expiry (Unix seconds).
+/// Expired sessions are purged first; fails if the store is full or the body is not a presentation.
+pub fn store_base_session(body: &str) -> Result<(String, i64), VerificationError> {
+    // Only accept bodies that are valid presentations on their own
+    PresentationJSON::from_json_str(body).map_err(|e| {
+        VerificationError::with_code("INVALID_BASE_SESSION", format!("Invalid base presentation: {}", e))
+    })?;
+    let base: Value = serde_json::from_str(body)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = now + config::get_session_ttl_secs();
+    let mut sessions = SESSIONS.lock().unwrap();
+    sessions.retain(|_, session| session.expires_at > now);
+    if sessions.len() >= config::get_max_sessions() {
+        return Err(VerificationError::with_code(
+            "SESSION_STORE_FULL",
+            "Session store is full, try again later",
+        ));
+    }
+
+    let mut id_bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut id_bytes);
+    let session_id = hex::encode(id_bytes);
+    sessions.insert(session_id.clone(), StoredSession { base, expires_at });
+    debug!(session_id = %session_id, active = sessions.len(), "Stored base session");
+    Ok((session_id, expires_at))
+}
+
+/// Resolves a request body that may reference a stored base session.
+/// If the body contains a `session_ref`, its remaining fields are merged over the stored base
+/// presentation (JSON merge patch semantics) and the combined presentation JSON is returned.
+/// Bodies without a `session_ref` are returned unchanged.
+pub fn resolve_session_ref(body: &str) -> Result<String, VerificationError> {
+    let mut delta: Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return Ok(body.to_string()), // Let verification report the JSON error
+    };
+    let session_ref = match delta.as_object_mut().and_then(|obj| obj.remove("session_ref")) {
+        Some(Value::String(id)) => id,
+        Some(_) => {
+            return Err(VerificationError::with_code(
+                "INVALID_SESSION_REF",
+                "session_ref must be a string",
+            ));
+        }
+        None => return Ok(body.to_string()),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let mut combined = {
+        let sessions = SESSIONS.lock().unwrap();
+        match sessions.get(&session_ref) {
+            Some(session) if session.expires_at > now => session.base.clone(),
+            _ => {
+                return Err(VerificationError::with_code(
+                    "UNKNOWN_SESSION",
+                    format!("Session '{}' does not exist or has expired", session_ref),
+                ));
+            }
+        }
+    };
+    merge_patch(&mut combined, delta);
+    Ok(combined.to_string())
+}
+
+/// Applies `patch` onto `target` following RFC 7396 (JSON merge patch)
+fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch_obj) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+            let target_obj = target.as_object_mut().unwrap();
+            for (key, value) in patch_obj {
+                if value.is_null() {
+                    target_obj.remove(&key);
+                } else {
+                    merge_patch(target_obj.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        other => *target = other,
+    }
+}
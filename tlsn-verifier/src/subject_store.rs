@@ -0,0 +1,219 @@
+use crate::config;
+use crate::types::{VerificationError, VerificationResult};
+use crate::verifier::response_field;
+use chrono::DateTime;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Latest connection time verified for a subject
+struct LastSeen {
+    timestamp: i64,  // Latest connection time (Unix seconds)
+    expires_at: i64, // Expiry of this entry (Unix seconds)
+}
+
+/// Latest verified connection time per server and subject, keyed by the hex SHA-256 of
+/// `server_name || 0x00 || subject` so raw subject ids are not kept in memory
+struct SubjectStore {
+    last_seen: Mutex<HashMap<String, LastSeen>>,
+    ttl_secs: i64,       // How long an entry is kept after its subject's last proof
+    max_subjects: usize, // Most subjects tracked at once
+}
+
+/// Global store, configured from `TLSN_VERIFIER_SUBJECT_TIMESTAMP_TTL_SECS` and
+/// `TLSN_VERIFIER_MAX_TRACKED_SUBJECTS`
+static SUBJECT_STORE: Lazy<SubjectStore> = Lazy::new(|| {
+    SubjectStore::new(config::get_subject_timestamp_ttl_secs(), config::get_max_tracked_subjects())
+});
+
+impl SubjectStore {
+    fn new(ttl_secs: i64, max_subjects: usize) -> Self {
+        Self {
+            last_seen: Mutex::new(HashMap::new()),
+            ttl_secs,
+            max_subjects,
+        }
+    }
+
+    /// Fails with `STALE_RELATIVE_TO_PREVIOUS` if the proof is older than the last one recorded
+    /// for its subject, and records its connection time if `record` is set
+    fn check(
+        &self,
+        result: &VerificationResult,
+        field: &str,
+        now: i64,
+        record: bool,
+    ) -> Result<(), VerificationError> {
+        let (key, timestamp) = subject_key(result, field)?;
+        let mut last_seen = self.last_seen.lock().unwrap();
+        last_seen.retain(|_, entry| entry.expires_at > now);
+        let tracked = last_seen.len();
+        let expires_at = now + self.ttl_secs;
+        match last_seen.get_mut(&key) {
+            Some(entry) if timestamp < entry.timestamp => Err(VerificationError::with_code(
+                "STALE_RELATIVE_TO_PREVIOUS",
+                format!(
+                    "Proof was captured at {}, before the latest proof verified for this subject",
+                    result.time
+                ),
+            )),
+            Some(entry) => {
+                if record {
+                    entry.timestamp = timestamp;
+                    entry.expires_at = expires_at;
+                }
+                Ok(())
+            }
+            None if !record => Ok(()),
+            None if tracked >= self.max_subjects => Err(VerificationError::with_code(
+                "SUBJECT_STORE_FULL",
+                "Too many subjects are tracked, try again later",
+            )),
+            None => {
+                last_seen.insert(key, LastSeen { timestamp, expires_at });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the store key of a result's subject and the result's connection time (Unix seconds).
+/// Fails with `SUBJECT_MISSING` if the response has no subject at `field`.
+fn subject_key(result: &VerificationResult, field: &str) -> Result<(String, i64), VerificationError> {
+    let subject = response_field(result, field).ok_or_else(|| {
+        VerificationError::with_code(
+            "SUBJECT_MISSING",
+            format!("Response from {} has no subject at '{}'", result.server_name, field),
+        )
+    })?;
+    let timestamp = DateTime::parse_from_rfc3339(&result.time)
+        .map_err(|e| VerificationError {
+            code: None,
+            message: format!("Invalid connection time '{}': {}", result.time, e),
+        })?
+        .timestamp();
+    let key = hex::encode(
+        Sha256::new()
+            .chain_update(result.server_name.as_bytes())
+            .chain_update([0u8])
+            .chain_update(subject.as_bytes())
+            .finalize(),
+    );
+    Ok((key, timestamp))
+}
+
+/// Rejects a proof older than the latest one recorded for the same subject by the same server
+/// (servers' clocks and update cycles differ, so they are tracked apart), to detect replay of
+/// an older score after a newer one was seen. Does not record the proof.
+/// A no-op unless `TLSN_VERIFIER_SUBJECT_FIELD` is set. Fails with `SUBJECT_MISSING` if the
+/// response has no subject, or `STALE_RELATIVE_TO_PREVIOUS` if the proof is older than the last
+/// one recorded.
+pub fn check(result: &VerificationResult) -> Result<(), VerificationError> {
+    match config::get_subject_field() {
+        Some(field) => SUBJECT_STORE.check(result, &field, chrono::Utc::now().timestamp(), false),
+        None => Ok(()),
+    }
+}
+
+/// Like `check`, and records the proof's connection time as the latest for its subject if it
+/// passes. Only called by `/verify-proof` once every other check passed, so proofs that are
+/// rejected, or only checked elsewhere, do not move a subject's latest time.
+/// Also fails with `SUBJECT_STORE_FULL` if too many subjects are tracked.
+pub fn check_and_record(result: &VerificationResult) -> Result<(), VerificationError> {
+    match config::get_subject_field() {
+        Some(field) => SUBJECT_STORE.check(result, &field, chrono::Utc::now().timestamp(), true),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_767_225_600; // 2026-01-01T00:00:00Z
+
+    /// A result from `server_name` captured at `time`, whose response body is `body`
+    fn result(server_name: &str, time: &str, body: &str) -> VerificationResult {
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        VerificationResult {
+            is_valid: true,
+            server_name: server_name.to_string(),
+            score: "742".to_string(),
+            verifying_key: "02ab".to_string(),
+            sent_hex_encoded: String::new(),
+            sent_readable: String::new(),
+            recv_hex_encoded: hex::encode(&response),
+            recv_readable: response,
+            time: time.to_string(),
+            result_id: String::new(),
+            response_status: 200,
+            provenance: Default::default(),
+            required_min_score: None,
+            timings: None,
+        }
+    }
+
+    fn code(outcome: Result<(), VerificationError>) -> Option<String> {
+        outcome.err().and_then(|e| e.code)
+    }
+
+    #[test]
+    fn older_proofs_are_rejected_once_a_newer_one_is_recorded() {
+        let store = SubjectStore::new(3600, 10);
+        let newer = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"id": "alice"}"#);
+        let older = result("api.bureau-a.com", "2025-12-31T00:00:00+00:00", r#"{"id": "alice"}"#);
+        assert!(store.check(&newer, "/id", NOW, true).is_ok());
+
+        assert_eq!(code(store.check(&older, "/id", NOW, false)), Some("STALE_RELATIVE_TO_PREVIOUS".to_string()));
+        assert_eq!(code(store.check(&older, "/id", NOW, true)), Some("STALE_RELATIVE_TO_PREVIOUS".to_string()));
+        // Resubmitting the latest proof still passes
+        assert!(store.check(&newer, "/id", NOW, true).is_ok());
+    }
+
+    #[test]
+    fn checking_without_recording_leaves_the_latest_time() {
+        let store = SubjectStore::new(3600, 10);
+        let newer = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"id": "alice"}"#);
+        let older = result("api.bureau-a.com", "2025-12-31T00:00:00+00:00", r#"{"id": "alice"}"#);
+        assert!(store.check(&newer, "/id", NOW, false).is_ok());
+        assert!(store.check(&older, "/id", NOW, true).is_ok());
+        assert!(store.check(&newer, "/id", NOW, false).is_ok());
+    }
+
+    #[test]
+    fn subjects_are_tracked_per_server_until_they_expire() {
+        let store = SubjectStore::new(3600, 10);
+        let newer = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"id": "alice"}"#);
+        let older_other_server = result("api.bureau-b.com", "2025-12-31T00:00:00+00:00", r#"{"id": "alice"}"#);
+        let older_other_subject = result("api.bureau-a.com", "2025-12-31T00:00:00+00:00", r#"{"id": "bob"}"#);
+        let older = result("api.bureau-a.com", "2025-12-31T00:00:00+00:00", r#"{"id": "alice"}"#);
+        assert!(store.check(&newer, "/id", NOW, true).is_ok());
+        assert!(store.check(&older_other_server, "/id", NOW, true).is_ok());
+        assert!(store.check(&older_other_subject, "/id", NOW, true).is_ok());
+
+        // The entry expires an hour after the subject's last recorded proof
+        assert!(store.check(&older, "/id", NOW + 3599, false).is_err());
+        assert!(store.check(&older, "/id", NOW + 3600, false).is_ok());
+    }
+
+    #[test]
+    fn new_subjects_beyond_the_limit_are_refused() {
+        let store = SubjectStore::new(3600, 1);
+        let alice = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"id": "alice"}"#);
+        let bob = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"id": "bob"}"#);
+        assert!(store.check(&alice, "/id", NOW, true).is_ok());
+        assert_eq!(code(store.check(&bob, "/id", NOW, true)), Some("SUBJECT_STORE_FULL".to_string()));
+        // Checking only does not need room, and known subjects are still updated
+        assert!(store.check(&bob, "/id", NOW, false).is_ok());
+        assert!(store.check(&alice, "/id", NOW, true).is_ok());
+    }
+
+    #[test]
+    fn responses_without_the_subject_are_rejected() {
+        let store = SubjectStore::new(3600, 10);
+        let anonymous = result("api.bureau-a.com", "2026-01-01T00:00:00+00:00", r#"{"value": 742}"#);
+        assert_eq!(code(store.check(&anonymous, "/id", NOW, false)), Some("SUBJECT_MISSING".to_string()));
+        assert_eq!(code(store.check(&anonymous, "$.id", NOW, true)), Some("SUBJECT_MISSING".to_string()));
+    }
+}
@@ -1,59 +1,164 @@
 
+use crate::config;
 use crate::types::TappdError;
+use hyper::body::Bytes;
 use hyper::{Body, Client, Request};
 use hyperlocal::{UnixClientExt, Uri};
 use serde_json::json;
 use hyper::Response;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// Path of the tappd Unix socket
+const TAPPD_SOCKET_PATH: &str = "/var/run/tappd.sock";
+/// Upper bound for the delay between reconnect attempts
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(2);
+/// Upper bound for the delay between retries after a server error
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+/// Longest part of a server error body quoted in the final error message
+const MAX_ERROR_BODY_LEN: usize = 200;
 
 
 pub async fn send_quote_request(
     custom_evidence : &str,
-) -> Result<Response<Body>, TappdError> {
-    println!("[send_quote_request] Sending quote request to Tappd service");
-    let client = Client::unix();
-    let uri: hyperlocal::Uri = Uri::new("/var/run/tappd.sock", "/prpc/Tappd.TdxQuote?json").into();
-    // Build HTTP POST request with JSON body
-    let req = Request::post(uri)
-        .header("Content-Type", "application/json")
-        .body(Body::from(custom_evidence.to_string()))
-        .map_err(|e| {
-            TappdError {
-                message: format!("Failed to build request: {}", e),
-            }
-        })?;
-    println!("[send_quote_request] Request built successfully: {:?}", req);
-    // Send the request to the tappd socket and await response
-    let res = client.request(req).await.map_err(|e| {
-        TappdError {
-            message: format!("Failed to send request: {}", e),
-        }
-    })?;
-    println!("[send_quote_request] Response received from Tappd service: {:?}", res);
+) -> Result<Response<Bytes>, TappdError> {
+    debug!("Sending quote request to Tappd service");
+    // Send the request to the tappd socket (reconnecting while it is unavailable, retrying
+    // server errors) and await response
+    let res = send_with_retry("/prpc/Tappd.TdxQuote?json", custom_evidence.to_string()).await?;
+    trace!(response = ?res, "Quote response received from Tappd service");
     Ok(res)
 }
 
 
-pub async fn send_key_request() -> Result<Response<Body>, TappdError> {
-    println!("[send_key_request] Requesting key material from Tappd service");
+pub async fn send_key_request() -> Result<Response<Bytes>, TappdError> {
+    debug!("Requesting key material from Tappd service");
+    // Send an empty JSON body to the tappd socket (retrying server errors) and await response
+    let res = send_with_retry("/prpc/Tappd.DeriveKey?json", json!({}).to_string()).await?;
+    trace!(response = ?res, "Key response received from Tappd service");
+    Ok(res)
+}
+
+
+/// Checks whether tappd answers an Info request right now, without reconnect retries
+pub async fn is_reachable() -> bool {
+    match send_once("/prpc/Tappd.Info?json", json!({}).to_string()).await {
+        Ok(res) => res.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// Why a single attempt to reach tappd failed
+enum AttemptError {
+    /// The socket is missing or refuses connections (e.g. tappd is restarting); worth retrying
+    Unavailable(String),
+    /// The request itself failed after connecting; retrying could repeat side effects
+    Failed(String),
+}
+
+/// POSTs a JSON body to `path` like `send_with_reconnect`, and resends it while tappd answers
+/// with a server error (5xx), up to `TLSN_VERIFIER_TAPPD_MAX_ATTEMPTS` times with exponential
+/// backoff from `TLSN_VERIFIER_TAPPD_RETRY_BASE_DELAY_MS`. Client errors (e.g. 400, 401) are
+/// returned without retrying. Only used for requests that are safe to repeat (quotes, keys).
+async fn send_with_retry(path: &str, body: String) -> Result<Response<Bytes>, TappdError> {
+    let max_attempts = config::get_tappd_max_attempts();
+    let mut delay = Duration::from_millis(config::get_tappd_retry_base_delay_ms());
+    let mut attempt = 1;
+    loop {
+        let res = send_with_reconnect(path, body.clone()).await?;
+        let status = res.status();
+        if !status.is_server_error() {
+            return Ok(res);
+        }
+        if attempt >= max_attempts {
+            let body = String::from_utf8_lossy(res.body());
+            return Err(TappdError {
+                message: format!(
+                    "Tappd returned {} after {} attempts: {}",
+                    status,
+                    attempt,
+                    body.chars().take(MAX_ERROR_BODY_LEN).collect::<String>()
+                ),
+            });
+        }
+        warn!(
+            status = status.as_u16(),
+            attempt,
+            max_attempts,
+            delay_ms = delay.as_millis() as u64,
+            "Tappd returned a server error, retrying"
+        );
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RETRY_BACKOFF);
+        attempt += 1;
+    }
+}
+
+/// POSTs a JSON body to `path` on the tappd socket.
+/// While the socket is missing or refusing connections, retries with exponential backoff
+/// for up to `TLSN_VERIFIER_TAPPD_RECONNECT_WINDOW_MS`; other failures are returned immediately.
+async fn send_with_reconnect(path: &str, body: String) -> Result<Response<Bytes>, TappdError> {
+    let window = Duration::from_millis(config::get_tappd_reconnect_window_ms());
+    let started = Instant::now();
+    let mut backoff = Duration::from_millis(config::get_tappd_reconnect_initial_backoff_ms());
+    let mut attempt = 1;
+    loop {
+        match send_once(path, body.clone()).await {
+            Ok(res) => return Ok(res),
+            Err(AttemptError::Failed(message)) => return Err(TappdError { message }),
+            Err(AttemptError::Unavailable(message)) => {
+                if started.elapsed() + backoff > window {
+                    return Err(TappdError {
+                        message: format!(
+                            "Tappd socket unavailable after {} attempts over {:?}: {}",
+                            attempt,
+                            started.elapsed(),
+                            message
+                        ),
+                    });
+                }
+                warn!(error = %message, backoff_ms = backoff.as_millis() as u64, "Tappd socket unavailable, retrying");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Makes a single POST request to the tappd socket and reads the whole response body.
+/// Fails if the response, body included, does not arrive within `TLSN_VERIFIER_TAPPD_TIMEOUT_MS`,
+/// so a hung tappd (even one that stalls mid-body) cannot tie up the calling worker indefinitely.
+async fn send_once(path: &str, body: String) -> Result<Response<Bytes>, AttemptError> {
+    if !Path::new(TAPPD_SOCKET_PATH).exists() {
+        return Err(AttemptError::Unavailable(format!("socket {} is missing", TAPPD_SOCKET_PATH)));
+    }
     let client = Client::unix();
-    let uri: hyperlocal::Uri = Uri::new("/var/run/tappd.sock", "/prpc/Tappd.DeriveKey?json").into();
+    let uri: hyperlocal::Uri = Uri::new(TAPPD_SOCKET_PATH, path).into();
 
-    // Build HTTP POST request with empty JSON body
+    // Build HTTP POST request with JSON body
     let req = Request::post(uri)
         .header("Content-Type", "application/json")
-        .body(Body::from(json!({}).to_string()))
-        .map_err(|e| {
-            TappdError {
-                message: format!("Failed to build request: {}", e),
+        .body(Body::from(body))
+        .map_err(|e| AttemptError::Failed(format!("Failed to build request: {}", e)))?;
+    trace!(request = ?req, "Sending request to Tappd");
+    let timeout = Duration::from_millis(config::get_tappd_timeout_ms());
+    let exchange = async {
+        let res = client.request(req).await.map_err(|e| {
+            if e.is_connect() {
+                AttemptError::Unavailable(format!("Failed to connect: {}", e))
+            } else {
+                AttemptError::Failed(format!("Failed to send request: {}", e))
             }
         })?;
-    println!("[send_key_request] Request built successfully: {:?}", req);
-    // Send the request to the tappd socket and await response
-    let res = client.request(req).await.map_err(|e| {
-        TappdError {
-            message: format!("Failed to send request: {}", e),
-        }
-    })?;
-    println!("[send_key_request] Response received from Tappd service: {:?}", res);
-    Ok(res)
-}
\ No newline at end of file
+        let (parts, body) = res.into_parts();
+        let bytes = hyper::body::to_bytes(body)
+            .await
+            .map_err(|e| AttemptError::Failed(format!("Failed to read response body: {}", e)))?;
+        Ok(Response::from_parts(parts, bytes))
+    };
+    tokio::time::timeout(timeout, exchange)
+        .await
+        .map_err(|_| AttemptError::Failed(format!("Tappd did not respond within {:?}", timeout)))?
+}
@@ -14,7 +14,15 @@ use p256::{
 use p256::pkcs8::DecodePrivateKey;
 
 use rand_core::OsRng;
-use sha2::{Digest, Sha512};
+use crate::hex_decode::decode_hex_skipping_whitespace;
+use crate::merkle::ProofStep;
+use crate::metrics::AttestationMetrics;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::Keccak256;
+use ring::hkdf;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{trace, warn};
 /// Represents a TLSNotary presentation in JSON form, including version info, data payload, and metadata.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -22,6 +30,8 @@ pub struct PresentationJSON {
     pub version: String,  // Version of the presentation format
     pub data: String,     // Hex-encoded serialized Presentation
     pub meta: Meta,       // Additional metadata such as notary URL
+    #[serde(default, alias = "notary_attestation")]
+    pub notary_attestation: Option<String>, // Optional hex-encoded TDX quote binding the notary key
 }
 
 /// Metadata associated with a presentation
@@ -44,6 +54,107 @@ pub struct VerificationResult {
     pub recv_hex_encoded: String,          // Hex-encoded received message
     pub recv_readable: String,             // Human-readable received message
     pub time: String,                      // Timestamp of verification
+    #[serde(default)]
+    pub result_id: String,                 // Stable content hash of the verified facts, see `compute_result_id`
+    #[serde(default)]
+    pub response_status: u16,              // Status code of the first response's status line
+    #[serde(default)]
+    pub provenance: BTreeMap<String, FieldProvenance>, // Transcript location of each extracted field, ordered by name so hashes are stable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_min_score: Option<u64>,   // Per-request threshold the score was checked against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<VerificationTimings>, // Step timings (only returned on `/verify-proof?debug=true`)
+}
+
+/// Maximum length of the caller's request id echoed into a `VerificationSummary`
+const SUMMARY_REQUEST_ID_MAX_LEN: usize = 128;
+
+/// Fixed-schema, one-line summary of a verification for security tooling.
+/// Never holds transcripts, keys or error messages (which may quote transcript bytes).
+#[derive(Debug, Serialize)]
+pub struct VerificationSummary {
+    pub ts: String,                  // RFC 3339 UTC time the summary was produced
+    pub server_name: Option<String>, // Verified TLS server name (valid proofs only)
+    pub score: Option<String>,       // Extracted score (valid proofs only)
+    pub valid: bool,                 // Whether the presentation verified
+    pub error_code: Option<String>,  // Machine-readable error code, if verification failed
+    pub client_id: String,           // Identifier of the API key that authorized the request, or `-`
+    pub request_id: Option<String>,  // Caller's X-Request-Id (printable ASCII only, truncated)
+    pub elapsed_ms: f64,             // Time spent verifying
+}
+
+impl VerificationSummary {
+    /// Summarizes a verification outcome. `request_id` is client-controlled, so anything but
+    /// printable ASCII is dropped and it is cut to `SUMMARY_REQUEST_ID_MAX_LEN` characters.
+    pub fn new(
+        result: &Result<VerificationResult, VerificationError>,
+        client_id: &str,
+        request_id: Option<&str>,
+        elapsed_ms: f64,
+    ) -> Self {
+        let request_id = request_id.map(|id| {
+            id.chars()
+                .filter(|c| c.is_ascii_graphic())
+                .take(SUMMARY_REQUEST_ID_MAX_LEN)
+                .collect::<String>()
+        });
+        let (server_name, score, valid, error_code) = match result {
+            Ok(verified) => (
+                Some(verified.server_name.clone()),
+                Some(verified.score.clone()),
+                verified.is_valid,
+                None,
+            ),
+            Err(e) => (None, None, false, Some(e.code.clone().unwrap_or_else(|| "VERIFICATION_FAILED".to_string()))),
+        };
+        Self {
+            ts: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            server_name,
+            score,
+            valid,
+            error_code,
+            client_id: client_id.to_string(),
+            request_id,
+            elapsed_ms,
+        }
+    }
+}
+
+/// Time spent in each step of `verify_proof`, in milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationTimings {
+    pub parse_ms: f64,  // Parsing the request JSON
+    pub decode_ms: f64, // Decoding the presentation
+    pub verify_ms: f64, // Cryptographic verification of the presentation
+    pub total_ms: f64,  // Whole verification, including policy checks
+}
+
+/// Direction of the transcript a field was extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptDirection {
+    Sent, // Request bytes sent by the prover
+    Recv, // Response bytes received from the server
+}
+
+/// Where an extracted field came from, so auditors can check it against the proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldProvenance {
+    pub transcript: TranscriptDirection, // Transcript holding the field
+    pub start: usize,                    // Start byte offset (inclusive) in that transcript
+    pub end: usize,                      // End byte offset (exclusive) in that transcript
+    pub authenticated: bool,             // Whether the whole range is authenticated by the proof
+}
+
+impl VerificationResult {
+    /// Computes the stable identifier of a verification as the lowercase hex SHA-256 of
+    /// `server_name || 0x0A || score || 0x0A || time || 0x0A || verifying_key`,
+    /// where every field is the UTF-8 string exactly as returned in the result
+    /// (`time` in RFC 3339, `verifying_key` hex-encoded).
+    pub fn compute_result_id(server_name: &str, score: &str, time: &str, verifying_key: &str) -> String {
+        let preimage = [server_name, score, time, verifying_key].join("\n");
+        hex::encode(Sha256::digest(preimage.as_bytes()))
+    }
 }
 
 
@@ -51,13 +162,26 @@ pub struct VerificationResult {
 /// Error that occurred during the verification process
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct VerificationError {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,              // Machine-readable error code, if any
     pub message: String,                   // Human-readable error message
 }
 
+impl VerificationError {
+    /// Creates a VerificationError tagged with a machine-readable code
+    pub fn with_code(code: &str, message: impl Into<String>) -> Self {
+        VerificationError {
+            code: Some(code.to_string()),
+            message: message.into(),
+        }
+    }
+}
+
 // Allows converting any Display-able error into a VerificationError
 impl<E: std::fmt::Display> From<E> for VerificationError {
     fn from(e: E) -> Self {
         VerificationError {
+            code: None,
             message: e.to_string(),
         }
     }
@@ -79,13 +203,26 @@ impl<E: std::fmt::Display> From<E> for TappdError {
 /// Error that occurred during attestation
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AttestationError {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub message: String,
 }
 
+impl AttestationError {
+    /// Creates an AttestationError tagged with a machine-readable code
+    pub fn with_code(code: &str, message: impl Into<String>) -> Self {
+        AttestationError {
+            code: Some(code.to_string()),
+            message: message.into(),
+        }
+    }
+}
+
 // Conversion implementation for AttestationError from any displayable error
 impl<E: std::fmt::Display> From<E> for AttestationError {
     fn from(e: E) -> Self {
         AttestationError {
+            code: None,
             message: e.to_string(),
         }
     }
@@ -111,15 +248,172 @@ impl<E: std::fmt::Display> From<E> for KeyManagerError {
 pub struct VerificationResponse {
     pub verification: Result<VerificationResult, VerificationError>, // Result of verification process
     pub attestation: Result<SignedAttestation, AttestationError>,    // Result of attestation (with signature)
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<AttestationMetrics>,                           // Attestation timings (`?debug=true` only)
+}
+
+/// Request body for checking that presentations from different servers are about the same subject
+#[derive(Debug, Deserialize)]
+pub struct CorrelateRequest {
+    pub presentations: Vec<Value>,               // Presentations to verify, e.g. a score proof and an income proof
+    pub subject_field: String,                   // JSON pointer or `$`-rooted JSONPath of the subject id in each response
+    #[serde(default)]
+    pub subject_fields: HashMap<String, String>, // Per-server-name overrides of `subject_field`
+}
+
+/// Presentations verified to be about the same subject
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CorrelationResult {
+    pub subject: String,                  // Subject id found in every response
+    pub results: Vec<VerificationResult>, // Verification result of each presentation, in request order
+}
+
+/// Wrapper for the correlation result and the attestation over it
+#[derive(Serialize, Deserialize)]
+pub struct CorrelationResponse {
+    pub verification: Result<CorrelationResult, VerificationError>, // Result of verifying and correlating
+    pub attestation: Result<SignedAttestation, AttestationError>,   // Attestation over `verification`
+}
+
+/// How the results of a batch verification are attested
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchAttestationMode {
+    Merkle,    // One quote over the Merkle root of all results, plus per-result inclusion proofs
+    PerResult, // One quote per result
+}
+
+/// A single entry of a batch verification response
+#[derive(Serialize, Clone)]
+pub struct BatchItem {
+    pub verification: Result<VerificationResult, VerificationError>, // Result of verifying this presentation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_hex_encoded: Option<String>,                             // Merkle leaf hash (merkle mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inclusion_proof: Option<Vec<ProofStep>>,                      // Path from the leaf to the root (merkle mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Result<SignedAttestation, AttestationError>>, // Own attestation (per_result mode)
+}
+
+/// Response of the batch verification endpoint
+#[derive(Serialize, Clone)]
+pub struct BatchVerificationResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,                                     // Id under which the batch is stored for paging
+    pub mode: BatchAttestationMode,                                   // Attestation mode used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merkle_root_hex_encoded: Option<String>,                      // Root committed to by the quote (merkle mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Result<SignedAttestation, AttestationError>>, // Single attestation over the root (merkle mode)
+    pub items: Vec<BatchItem>,                                        // Per-presentation results, in request order
+}
+
+/// Processing status of a stored batch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Pending,
+    Complete,
+}
+
+/// One page of a stored batch's results
+#[derive(Serialize)]
+pub struct BatchPageResponse {
+    pub batch_id: String,                                             // Id of the batch
+    pub status: BatchStatus,                                          // Whether results are available yet
+    pub total: usize,                                                 // Total number of items in the batch
+    pub offset: usize,                                                // Index of the first item in this page
+    pub limit: usize,                                                 // Maximum number of items per page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<BatchAttestationMode>,                           // Attestation mode used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merkle_root_hex_encoded: Option<String>,                      // Root committed to by the quote (merkle mode)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Result<SignedAttestation, AttestationError>>, // Single attestation over the root (merkle mode)
+    pub items: Vec<BatchItem>,                                        // Items `offset..offset + limit`
+}
+
+/// A single result of a Merkle-mode batch with its inclusion proof under the attested root
+#[derive(Serialize)]
+pub struct InclusionProofResponse {
+    pub batch_id: String,                                             // Id of the batch
+    pub index: usize,                                                 // Position of the result in the batch
+    pub total: usize,                                                 // Total number of items in the batch
+    pub merkle_root_hex_encoded: String,                              // Root committed to by the quote
+    pub attestation: Result<SignedAttestation, AttestationError>,     // Single attestation over the root
+    pub verification: Result<VerificationResult, VerificationError>, // Result of verifying this presentation
+    pub leaf_hex_encoded: String,                                     // Merkle leaf hash of `verification`
+    pub inclusion_proof: Vec<ProofStep>,                              // Path from the leaf to the root
+}
+
+/// Everything a relying party needs to re-verify a proof later, covered by the bundle signature
+#[derive(Serialize)]
+pub struct BundleContents {
+    pub verification: Result<VerificationResult, VerificationError>, // Result of verification process
+    pub attestation: SignedAttestation,                              // Quote, certificate chain and quote signature
+    pub event_log: String,                                           // JSON-encoded event log of the quote
+}
+
+/// Self-contained, signed archive of a verification and its attestation
+#[derive(Serialize)]
+pub struct AttestationBundle {
+    pub bundle_version: u32,                          // Layout version of the bundle
+    #[serde(flatten)]
+    pub contents: BundleContents,                     // Signed contents
+    pub bundle_digest_hex_encoded: String,            // SHA-256 of the JSON-serialized contents
+    pub bundle_signature_hex_encoded: String,         // Signature over the hex-encoded digest
+    pub bundle_signing_mode: SigningMode,             // Scheme used for the bundle signature
+    pub bundle_algorithm: SignatureAlgorithm,         // Exact algorithm of the bundle signature
+    pub bundle_verifying_key_hex_encoded: String,     // Key verifying the bundle signature
+}
+
+/// Forward-compatible wrapper of a response. The signature covers the schema version together
+/// with the payload, so stored artifacts stay verifiable as the payload's shape evolves.
+#[derive(Serialize)]
+pub struct SignedEnvelope<T> {
+    pub schema_version: u32,          // Version of the payload's format
+    pub payload: T,                   // The wrapped response
+    pub signature: EnvelopeSignature, // Signature over `schema_version` and `payload`
+}
+
+/// Signature of a `SignedEnvelope`
+#[derive(Serialize)]
+pub struct EnvelopeSignature {
+    pub digest_hex_encoded: String,        // SHA-256 of the JSON-serialized `{schema_version, payload}`
+    pub signature_hex_encoded: String,     // Signature over the hex-encoded digest
+    pub signing_mode: SigningMode,         // Scheme used for the signature
+    pub algorithm: SignatureAlgorithm,     // Exact algorithm of the signature
+    pub verifying_key_hex_encoded: String, // Key verifying the signature
 }
 
 /// Resulting signed attestation after successful proof
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct SignedAttestation {
     pub quote: String,                                // Hex-encoded attestation quote
+    #[serde(skip)]
+    pub event_log: Option<String>,                    // JSON-encoded event log (only exposed in bundles)
     pub signature_hex_encoded: String,                // Hex-encoded signature over the attestation
     pub verifying_key_hex_encoded: String,            // Verifying key used to generate the signature
     pub verifying_key_certificate_chain: Option<Vec<String>>, // Optional PEM certificate chain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<SignatureScheme>,              // Key type of the enclave key (absent in older saved responses)
+    pub signing_mode: SigningMode,                    // Scheme used to produce the signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<SignatureAlgorithm>,        // Exact algorithm of the signature (absent in older saved responses)
+    pub report_data_hash_algorithm: ReportDataHashAlgorithm, // Hash used to derive report_data
+    pub report_data_input: String,                    // Description of the pre-hash input of report_data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_id: Option<u8>,                      // ECDSA recovery id (keccak256 mode only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_address: Option<String>,               // Ethereum address of the signer (keccak256 mode only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator_signature_hex: Option<String>,       // P-256 signature by the operator key over the same message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator_verifying_key_hex: Option<String>,   // Operator verifying key (SEC1, hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator_algorithm: Option<SignatureAlgorithm>, // Algorithm of the operator signature (always ECDSA-P256-SHA256)
+    #[serde(skip)]
+    pub metrics: Option<AttestationMetrics>,          // Timings of this attestation (only exposed on `?debug=true`)
 }
 
 impl PresentationJSON {
@@ -128,13 +422,75 @@ impl PresentationJSON {
         return serde_json::from_str(json);
     }
 
-    /// Decodes the presentation hex string into a Presentation struct
-    pub fn to_presentation(&self) -> Result<Presentation, Box<dyn std::error::Error>> {
-        let tmp_data: String = self.data.chars().filter(|c| !c.is_whitespace()).collect();
-        let raw = hex::decode(&tmp_data)?;
-        let presentation: Presentation = bincode::deserialize(&raw)?;
+    /// Decodes the presentation hex string into a Presentation struct.
+    /// Common decoding failures are mapped to coded, actionable errors.
+    pub fn to_presentation(&self) -> Result<Presentation, VerificationError> {
+        let raw = decode_hex_skipping_whitespace(&self.data).map_err(|e| {
+            VerificationError::with_code(
+                "INVALID_HEX",
+                format!("Invalid presentation encoding: data is not valid hex ({})", e),
+            )
+        })?;
+        if raw.is_empty() {
+            return Err(VerificationError::with_code(
+                "EMPTY_PRESENTATION",
+                "Invalid presentation encoding: presentation data is empty",
+            ));
+        }
+
+        // Deserialize from a cursor so that unconsumed trailing bytes can be detected
+        let mut cursor = std::io::Cursor::new(raw.as_slice());
+        let presentation: Presentation = bincode::deserialize_from(&mut cursor)
+            .map_err(|e| self.describe_bincode_error(*e, raw.len()))?;
+        let consumed = cursor.position() as usize;
+        if consumed < raw.len() {
+            return Err(VerificationError::with_code(
+                "TRAILING_BYTES",
+                format!(
+                    "Invalid presentation encoding: {} trailing bytes after the presentation ({} of {} bytes consumed); \
+                     the data may be concatenated or corrupted",
+                    raw.len() - consumed,
+                    consumed,
+                    raw.len()
+                ),
+            ));
+        }
         Ok(presentation)
     }
+
+    /// Maps a bincode failure to a coded error with a hint at the likely cause
+    fn describe_bincode_error(&self, error: bincode::ErrorKind, len: usize) -> VerificationError {
+        match error {
+            bincode::ErrorKind::Io(ref io) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+                VerificationError::with_code(
+                    "TRUNCATED_PRESENTATION",
+                    format!(
+                        "Invalid presentation encoding: data ended unexpectedly after {} bytes; \
+                         the proof was likely truncated while copying",
+                        len
+                    ),
+                )
+            }
+            bincode::ErrorKind::InvalidTagEncoding(_)
+            | bincode::ErrorKind::InvalidBoolEncoding(_)
+            | bincode::ErrorKind::InvalidCharEncoding
+            | bincode::ErrorKind::InvalidUtf8Encoding(_)
+            | bincode::ErrorKind::SizeLimit => VerificationError::with_code(
+                "PRESENTATION_VERSION_MISMATCH",
+                format!(
+                    "Invalid presentation encoding: {}; the presentation was likely produced by a \
+                     different TLSN version than '{}' (declared: '{}')",
+                    error,
+                    crate::config::get_tlsn_core_version(),
+                    self.version
+                ),
+            ),
+            other => VerificationError::with_code(
+                "INVALID_PRESENTATION_ENCODING",
+                format!("Invalid presentation encoding: {}", other),
+            ),
+        }
+    }
 }
 
 /// Represents a single entry in the attestation event log
@@ -147,6 +503,93 @@ pub struct EventLog {
     pub event_payload: String,  // Associated payload as a string
 }
 
+/// Request body for attesting over a digest the client computed itself (e.g. after verifying a proof elsewhere)
+#[derive(Debug, Deserialize)]
+pub struct AttestDigestRequest {
+    pub digest_hex: String,    // 32- or 64-byte digest, hex (optionally `0x`-prefixed)
+}
+
+/// Request body for comparing another verifier's public key against this instance's
+#[derive(Debug, Deserialize)]
+pub struct CompareKeyRequest {
+    pub verifying_key_hex_encoded: String,    // Other verifier's public key (SEC1, hex)
+    pub key_source: Option<KeySource>,        // Key source reported by the other verifier, if known
+}
+
+/// Result of comparing another verifier's public key against this instance's
+#[derive(Debug, Serialize)]
+pub struct CompareKeyResponse {
+    pub matches: bool,                        // Whether both verifiers hold the same public key
+    pub local_key_source: KeySource,          // How this instance's key was provisioned
+    pub remote_key_source: Option<KeySource>, // How the other instance's key was provisioned
+}
+
+/// Request body for evicting cached verification results and quotes
+#[derive(Debug, Deserialize)]
+pub struct InvalidateCacheRequest {
+    pub target: String, // Hex SHA-256 of a presentation body, or `all`
+}
+
+/// Number of entries evicted from each cache
+#[derive(Debug, Serialize)]
+pub struct InvalidateCacheResponse {
+    pub result_cache_evicted: usize, // Cached verification results removed
+    pub quote_cache_evicted: usize,  // Cached quotes removed (only for `all`)
+    pub evicted: usize,              // Total entries removed
+}
+
+/// Result of rotating the signing key
+#[derive(Debug, Serialize)]
+pub struct RotateKeyResponse {
+    pub verifying_key_hex_encoded: String,                  // New public key (uncompressed SEC1, hex)
+    pub previous_verifying_key_hex_encoded: Option<String>, // Key that was replaced, if any
+    pub key_source: KeySource,                              // How the new key was provisioned
+    pub quote_cache_evicted: usize,                         // Cached quotes removed
+}
+
+/// Response describing this verifier's public signing key
+#[derive(Debug, Serialize)]
+pub struct PubKeyResponse {
+    pub verifying_key_hex_encoded: String,    // Public key (uncompressed SEC1 for P-256, raw for Ed25519, hex)
+    pub scheme: SignatureScheme,              // Key type of the public key
+    pub key_source: KeySource,                // How the key was provisioned
+    pub verifying_key_certificate_chain: Option<Vec<String>>, // Optional PEM certificate chain
+    pub signature_nonce_policy: &'static str, // How ECDSA nonces are chosen, see `SIGNATURE_NONCE_POLICY`
+}
+
+/// ECDSA nonce policy of every signing path: nonces are derived deterministically from the key
+/// and message digest (RFC 6979), so signatures are reproducible for relying parties
+pub const SIGNATURE_NONCE_POLICY: &str = "rfc6979";
+
+/// Signed heartbeat proving the signing key is live
+#[derive(Debug, Serialize)]
+pub struct HeartbeatResponse {
+    pub timestamp: i64,                       // Unix seconds at which the heartbeat was signed
+    pub message: String,                      // Signed message: `tlsn-verifier heartbeat <timestamp>`
+    pub signature_hex_encoded: String,        // Signature over the hex encoding of `message`
+    pub verifying_key_hex_encoded: String,    // Key that verifies the signature
+    pub signing_mode: SigningMode,            // Scheme used to produce the signature
+    pub algorithm: SignatureAlgorithm,        // Exact algorithm of the signature
+}
+
+/// Response describing the running verifier build and the TLSN version it accepts
+#[derive(Debug, Serialize)]
+pub struct VersionResponse {
+    pub name: String,                         // Crate name
+    pub version: String,                      // Crate version
+    pub accepted_tlsn_core_version: String,   // TLSN core version accepted for presentations
+    pub accepted_tlsn_core_versions: Vec<String>, // All accepted TLSN core versions (`ACCEPTED_VERSIONS`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accepted_tlsn_core_version_range: Option<String>, // Semver range replacing the exact version, if set
+}
+
+/// Response returned after storing a base presentation session
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub session_id: String,                   // Id to pass as `session_ref` in later delta proofs
+    pub expires_at: i64,                      // Expiry of the stored session (Unix seconds)
+}
+
 /// Response containing a derived key and its associated certificate chain
 #[derive(Serialize, Deserialize)]
 pub struct GetKeyResponse {
@@ -167,7 +610,7 @@ impl GetKeyResponse {
 }
 
 /// Response containing a quote and associated event log (for attestation)
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GetQuoteResponse {
     pub quote: String,         // Hex-encoded quote
     pub event_log: String,     // JSON-encoded event log
@@ -228,16 +671,263 @@ pub struct TcbInfo {
 
 
 pub struct KeyMaterial {
-    pub signing_key: SigningKey,
+    pub signing_key: SigningKey,                // P-256 master key; other schemes derive their keys from it
     pub source: KeySource,
     pub certificate_chain: Option<Vec<String>>, // Chain of x509 certs, PEM-encoded
+    pub scheme: SignatureScheme,                // Scheme of `sign_message`, `encode_verify_key` and `verifying_key`
 }
 
-/// Indicates how the key was provisioned
+/// Salt of the HKDF deriving per-scheme keys from the master key
+const KEY_DERIVATION_SALT: &[u8] = b"tlsn-verifier key derivation";
+/// HKDF info label of the derived Ed25519 seed
+const ED25519_KEY_LABEL: &[u8] = b"tlsn-verifier/ed25519/v1";
+/// HKDF info label of the derived secp256k1 (EVM) secret
+const SECP256K1_KEY_LABEL: &[u8] = b"tlsn-verifier/secp256k1/v1";
+
+/// Key type of the enclave signing key, selected via `TLSN_VERIFIER_SIG_SCHEME`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureScheme {
+    #[default]
+    P256,    // ECDSA over P-256 with the master key itself
+    Ed25519, // Ed25519 (RFC 8032) with a key derived from the master key
+}
+
+/// Public key of `KeyMaterial` in its signature scheme
 #[derive(Debug, Clone, PartialEq)]
+pub enum SchemeVerifyingKey {
+    P256(VerifyingKey), // P-256 public key
+    Ed25519([u8; 32]),  // Raw Ed25519 public key
+}
+
+/// Signature scheme used when signing attestation payloads
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningMode {
+    P256Sha256,           // ECDSA over P-256 with the default SHA-256 prehash
+    Keccak256Recoverable, // Recoverable secp256k1 ECDSA over keccak256(message), for `ecrecover`
+    Ed25519,              // Ed25519 (RFC 8032) with a key derived from the enclave key
+}
+
+/// Exact signature algorithm (curve and hash) of a signature, declared next to it in every
+/// signed output so clients need not infer it from `signing_mode`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SignatureAlgorithm {
+    #[serde(rename = "ECDSA-P256-SHA256")]
+    EcdsaP256Sha256,     // ECDSA over P-256, SHA-256 prehash, DER signature
+    #[serde(rename = "ECDSA-K256-KECCAK256")]
+    EcdsaK256Keccak256,  // ECDSA over secp256k1, keccak256 prehash, `r || s || v` signature
+    #[serde(rename = "Ed25519")]
+    Ed25519,             // Ed25519 (RFC 8032)
+}
+
+impl SigningMode {
+    /// Native signing mode of a signature scheme, used unless the keccak256 mode is selected
+    pub fn for_scheme(scheme: SignatureScheme) -> Self {
+        match scheme {
+            SignatureScheme::P256 => SigningMode::P256Sha256,
+            SignatureScheme::Ed25519 => SigningMode::Ed25519,
+        }
+    }
+
+    /// Signature algorithm produced by this signing mode
+    pub fn algorithm(self) -> SignatureAlgorithm {
+        match self {
+            SigningMode::P256Sha256 => SignatureAlgorithm::EcdsaP256Sha256,
+            SigningMode::Keccak256Recoverable => SignatureAlgorithm::EcdsaK256Keccak256,
+            SigningMode::Ed25519 => SignatureAlgorithm::Ed25519,
+        }
+    }
+}
+
+/// Hash algorithm used to derive the quote's report_data from the attested payload
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportDataHashAlgorithm {
+    Sha512,    // 64-byte digest, fills report_data exactly
+    Sha256,    // 32-byte digest, zero-padded by the quote provider
+    Keccak256, // 32-byte digest, zero-padded by the quote provider
+}
+
+impl ReportDataHashAlgorithm {
+    /// Algorithms accepted in `TLSN_VERIFIER_REPORT_DATA_HASH_ALGORITHM`
+    pub const ALLOWED: [&'static str; 3] = ["sha512", "sha256", "keccak256"];
+
+    /// Parses an algorithm name from the allowlist
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha512" => Some(ReportDataHashAlgorithm::Sha512),
+            "sha256" => Some(ReportDataHashAlgorithm::Sha256),
+            "keccak256" => Some(ReportDataHashAlgorithm::Keccak256),
+            _ => None,
+        }
+    }
+}
+
+/// Non-cryptographic checks of the verification pipeline. Each check runs at a fixed stage,
+/// as soon as its inputs are available; `TLSN_VERIFIER_PIPELINE` orders the checks within a
+/// stage and disables the ones it leaves out. Cryptographic verification always runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineCheck {
+    Version,               // Presentation version is accepted for some server
+    ServerNameHint,        // Unverified SNI name is accepted, to fail fast before the crypto
+    NotaryAttestation,     // Notary key is bound in the notary's attestation quote
+    NotaryKeyStrength,     // Notary key algorithm is allowed and the key is a valid point
+    ServerName,            // Verified server name is accepted
+    ServerVersion,         // Presentation version is accepted for the server
+    CertFingerprint,       // Leaf certificate matches a pinned fingerprint
+    TimeWindow,            // Proof was captured inside the accepted time-of-day window
+    AuthenticatedFraction, // Enough of the transcript is authenticated
+    TranscriptRegions,     // Transcript is not split into too many (un)authenticated regions
+    HostHeader,            // Host header is unambiguous and matches the server name
+    HostPort,              // Host header port matches the expected port
+    AuthorizationScheme,   // Request carried an authenticated Authorization scheme
+    RequiredHeaders,       // Request carried each required header with its value
+    RequestPath,           // Request is a GET for an accepted path
+    ExtraRequests,         // Transcript has no requests beyond the allowed number
+    ResponseComplete,      // First response is not truncated
+    ResponseStatus,        // First response has a 2xx status code
+    ResponseCharset,       // Response charset is known and accepted
+    ResponseSchema,        // Response body conforms to the configured JSON Schema
+    BodyTimestamp,         // Response body's own timestamp is fresh
+    CriticalRegions,       // Request line, Host header and score are authenticated
+    MinScore,              // Score meets the server's minimum
+}
+
+/// Points of the verification at which pipeline checks run, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStage {
+    Parsed,     // Presentation JSON parsed, before decoding
+    Decoded,    // Presentation decoded, before cryptographic verification
+    Verified,   // Cryptographically verified and server name selected
+    Transcript, // Transcript extracted
+    Request,    // Request line and Host header parsed
+    Response,   // First response framed
+    Scored,     // Score extracted
+}
+
+impl PipelineCheck {
+    /// All checks, in the default pipeline order
+    pub const ALL: [PipelineCheck; 23] = [
+        PipelineCheck::Version,
+        PipelineCheck::ServerNameHint,
+        PipelineCheck::NotaryAttestation,
+        PipelineCheck::NotaryKeyStrength,
+        PipelineCheck::ServerName,
+        PipelineCheck::ServerVersion,
+        PipelineCheck::CertFingerprint,
+        PipelineCheck::TimeWindow,
+        PipelineCheck::AuthenticatedFraction,
+        PipelineCheck::TranscriptRegions,
+        PipelineCheck::HostHeader,
+        PipelineCheck::HostPort,
+        PipelineCheck::AuthorizationScheme,
+        PipelineCheck::RequiredHeaders,
+        PipelineCheck::RequestPath,
+        PipelineCheck::ExtraRequests,
+        PipelineCheck::ResponseComplete,
+        PipelineCheck::ResponseStatus,
+        PipelineCheck::ResponseCharset,
+        PipelineCheck::ResponseSchema,
+        PipelineCheck::BodyTimestamp,
+        PipelineCheck::CriticalRegions,
+        PipelineCheck::MinScore,
+    ];
+
+    /// Name of the check in `TLSN_VERIFIER_PIPELINE`
+    pub fn name(self) -> &'static str {
+        match self {
+            PipelineCheck::Version => "version",
+            PipelineCheck::ServerNameHint => "server_name_hint",
+            PipelineCheck::NotaryAttestation => "notary_attestation",
+            PipelineCheck::NotaryKeyStrength => "notary_key_strength",
+            PipelineCheck::ServerName => "server_name",
+            PipelineCheck::ServerVersion => "server_version",
+            PipelineCheck::CertFingerprint => "cert_fingerprint",
+            PipelineCheck::TimeWindow => "time_window",
+            PipelineCheck::AuthenticatedFraction => "authenticated_fraction",
+            PipelineCheck::TranscriptRegions => "transcript_regions",
+            PipelineCheck::HostHeader => "host_header",
+            PipelineCheck::HostPort => "host_port",
+            PipelineCheck::AuthorizationScheme => "authorization_scheme",
+            PipelineCheck::RequiredHeaders => "required_headers",
+            PipelineCheck::RequestPath => "request_path",
+            PipelineCheck::ExtraRequests => "extra_requests",
+            PipelineCheck::ResponseComplete => "response_complete",
+            PipelineCheck::ResponseStatus => "response_status",
+            PipelineCheck::ResponseCharset => "response_charset",
+            PipelineCheck::ResponseSchema => "response_schema",
+            PipelineCheck::BodyTimestamp => "body_timestamp",
+            PipelineCheck::CriticalRegions => "critical_regions",
+            PipelineCheck::MinScore => "min_score",
+        }
+    }
+
+    /// Looks up a check by its name in `TLSN_VERIFIER_PIPELINE`
+    pub fn from_name(name: &str) -> Option<PipelineCheck> {
+        PipelineCheck::ALL.into_iter().find(|check| check.name() == name)
+    }
+
+    /// Checks run when no pipeline is configured: all but `server_name_hint`, whose fail-fast
+    /// needs an extra serialization of the whole presentation
+    pub fn default_pipeline() -> Vec<PipelineCheck> {
+        PipelineCheck::ALL
+            .into_iter()
+            .filter(|check| *check != PipelineCheck::ServerNameHint)
+            .collect()
+    }
+
+    /// Stage at which the check's inputs are available
+    pub fn stage(self) -> PipelineStage {
+        match self {
+            PipelineCheck::Version => PipelineStage::Parsed,
+            PipelineCheck::ServerNameHint | PipelineCheck::NotaryAttestation | PipelineCheck::NotaryKeyStrength => {
+                PipelineStage::Decoded
+            }
+            PipelineCheck::ServerName
+            | PipelineCheck::ServerVersion
+            | PipelineCheck::CertFingerprint
+            | PipelineCheck::TimeWindow => PipelineStage::Verified,
+            PipelineCheck::AuthenticatedFraction | PipelineCheck::TranscriptRegions => PipelineStage::Transcript,
+            PipelineCheck::HostHeader
+            | PipelineCheck::HostPort
+            | PipelineCheck::AuthorizationScheme
+            | PipelineCheck::RequiredHeaders
+            | PipelineCheck::RequestPath
+            | PipelineCheck::ExtraRequests => PipelineStage::Request,
+            PipelineCheck::ResponseComplete
+            | PipelineCheck::ResponseStatus
+            | PipelineCheck::ResponseCharset
+            | PipelineCheck::ResponseSchema
+            | PipelineCheck::BodyTimestamp => PipelineStage::Response,
+            PipelineCheck::CriticalRegions | PipelineCheck::MinScore => PipelineStage::Scored,
+        }
+    }
+}
+
+/// How transcripts with more than one Host header are handled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultipleHostHeadersPolicy {
+    Reject,           // Any repeated Host header is rejected
+    RequireAgreement, // Repeated Host headers are accepted only if they all name the same host
+}
+
+/// Which source of the server name is trusted for the accepted-name check
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerNameSource {
+    Sni,         // The name the client sent in SNI (client-controlled)
+    Certificate, // A DNS name the server's leaf certificate is valid for (SAN)
+    Both,        // The SNI name, which the leaf certificate must also be valid for
+}
+
+/// Indicates how the key was provisioned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum KeySource {
     Tappd,   // Key was provisioned via Tappd
     Random,  // Key was generated locally
+    Operator, // Operator-held offline key loaded from a PEM file (secondary signer)
 }
 
 impl KeyMaterial {
@@ -248,15 +938,27 @@ impl KeyMaterial {
             signing_key,
             source: KeySource::Random,
             certificate_chain: None,
+            scheme: SignatureScheme::P256,
         }
     }
 
+    /// Create KeyMaterial from the operator's offline P-256 key (PKCS#8 PEM)
+    pub fn from_operator_pem(pem: &str) -> Result<Self, String> {
+        let signing_key = SigningKey::from_pkcs8_pem(pem).map_err(|e| e.to_string())?;
+        Ok(Self {
+            signing_key,
+            source: KeySource::Operator,
+            certificate_chain: None,
+            scheme: SignatureScheme::P256,
+        })
+    }
+
     /// Create KeyMaterial from a response returned by Tappd
     pub fn from_get_key_response(response: &GetKeyResponse) -> Result<Self, String> {
         let signing_key = match SigningKey::from_pkcs8_pem(&response.key) {
             Ok(key) => key,
             Err(e) => {
-                eprintln!("Failed to create signing key from Tappd key: {}", e);
+                warn!(error = %e, "Failed to create signing key from Tappd key, using a random key");
                 return Ok(KeyMaterial::new_random());
             }
         };
@@ -264,16 +966,45 @@ impl KeyMaterial {
             signing_key,
             source: KeySource::Tappd,
             certificate_chain: Some(response.certificate_chain.clone()),
+            scheme: SignatureScheme::P256,
+        })
+    }
+
+    /// Recreate locally generated KeyMaterial from its 32-byte secret scalar
+    pub fn from_random_key_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let signing_key = SigningKey::from_slice(bytes).map_err(|e| e.to_string())?;
+        Ok(Self {
+            signing_key,
+            source: KeySource::Random,
+            certificate_chain: None,
+            scheme: SignatureScheme::P256,
         })
     }
 
-    /// Returns the raw public key bytes in uncompressed format (04 || X || Y)
+    /// Switches the scheme used by `sign_message`, `encode_verify_key` and `verifying_key`
+    pub fn with_scheme(mut self, scheme: SignatureScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Derives a 32-byte secret for another signature scheme from the master key with
+    /// HKDF-SHA256, so no secret is shared between schemes
+    fn derive_scheme_secret(&self, label: &[u8]) -> [u8; 32] {
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, KEY_DERIVATION_SALT).extract(&self.signing_key.to_bytes());
+        let mut secret = [0u8; 32];
+        prk.expand(&[label], hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut secret))
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        secret
+    }
+
+    /// Returns the raw public key bytes of the scheme: uncompressed SEC1 (04 || X || Y) for
+    /// P-256, the 32-byte public key for Ed25519
     pub fn public_key_bytes(&self) -> Vec<u8> {
-        self.signing_key
-            .verifying_key()
-            .to_encoded_point(false)
-            .as_bytes()
-            .to_vec()
+        match self.verifying_key() {
+            SchemeVerifyingKey::P256(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+            SchemeVerifyingKey::Ed25519(key) => key.to_vec(),
+        }
     }
 
     /// Returns hex-encoded public key
@@ -282,19 +1013,36 @@ impl KeyMaterial {
         hex::encode(pub_key)
     }
 
-    /// Returns the verifying key corresponding to the signing key
-    pub fn verifying_key(&self) -> VerifyingKey {
-        self.signing_key.verifying_key().clone()
+    /// Returns the verifying key of the scheme
+    pub fn verifying_key(&self) -> SchemeVerifyingKey {
+        match self.scheme {
+            SignatureScheme::P256 => SchemeVerifyingKey::P256(*self.signing_key.verifying_key()),
+            SignatureScheme::Ed25519 => {
+                let mut key = [0u8; 32];
+                key.copy_from_slice(self.ed25519_key_pair().public_key().as_ref());
+                SchemeVerifyingKey::Ed25519(key)
+            }
+        }
     }
 
-    /// Constructs verifying key from a hex-encoded public key string
+    /// Constructs a verifying key of the scheme from a hex-encoded public key string
     pub fn verifying_key_from_hex_encoded(
         &self,
         hex_encoded: &str,
-    ) -> Result<VerifyingKey, String> {
+    ) -> Result<SchemeVerifyingKey, String> {
         let bytes = hex::decode(hex_encoded).map_err(|e| e.to_string())?;
-        let point = EncodedPoint::from_bytes(&bytes).map_err(|e| e.to_string())?;
-        VerifyingKey::from_encoded_point(&point).map_err(|e| e.to_string())
+        match self.scheme {
+            SignatureScheme::P256 => {
+                let point = EncodedPoint::from_bytes(&bytes).map_err(|e| e.to_string())?;
+                VerifyingKey::from_encoded_point(&point)
+                    .map(SchemeVerifyingKey::P256)
+                    .map_err(|e| e.to_string())
+            }
+            SignatureScheme::Ed25519 => bytes
+                .try_into()
+                .map(SchemeVerifyingKey::Ed25519)
+                .map_err(|bytes: Vec<u8>| format!("Expected a 32-byte Ed25519 key, got {} bytes", bytes.len())),
+        }
     }
 
     /// Computes a report hash (SHA-512) of the public key to embed in attestation
@@ -304,9 +1052,166 @@ impl KeyMaterial {
         format!("0x{}", hex::encode(hash))
     }
 
-    /// Signs the given message with the private key
-    pub fn sign_message(&self, message: &[u8]) -> Signature {
-        println!("[sign_message] Signing message with key source: {:?}", self.source);
-        self.signing_key.sign(message)
+    /// Signs the given message with the key of the scheme: a 64-byte `r || s` P-256 ECDSA/SHA-256
+    /// signature or a 64-byte Ed25519 signature.
+    /// Both are deterministic (RFC 6979 nonces for ECDSA), so equal messages yield equal signatures.
+    pub fn sign_message(&self, message: &[u8]) -> Vec<u8> {
+        trace!(key_source = ?self.source, scheme = ?self.scheme, "Signing message");
+        match self.scheme {
+            SignatureScheme::P256 => {
+                let signature: Signature = self.signing_key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            SignatureScheme::Ed25519 => self.ed25519_key_pair().sign(message).as_ref().to_vec(),
+        }
+    }
+
+    /// Returns the secp256k1 signing key derived from the master key,
+    /// used for EVM-compatible (`ecrecover`) signatures
+    pub fn evm_signing_key(&self) -> Result<k256::ecdsa::SigningKey, String> {
+        let secret = self.derive_scheme_secret(SECP256K1_KEY_LABEL);
+        k256::ecdsa::SigningKey::from_bytes(&secret.into()).map_err(|e| e.to_string())
+    }
+
+    /// Returns the uncompressed secp256k1 public key bytes (04 || X || Y) of the EVM signing key
+    pub fn evm_public_key_bytes(&self) -> Result<Vec<u8>, String> {
+        Ok(self
+            .evm_signing_key()?
+            .verifying_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec())
+    }
+
+    /// Returns the Ed25519 key pair seeded with a secret derived from the master key
+    fn ed25519_key_pair(&self) -> Ed25519KeyPair {
+        let seed = self.derive_scheme_secret(ED25519_KEY_LABEL);
+        Ed25519KeyPair::from_seed_unchecked(&seed).expect("a 32-byte seed is a valid Ed25519 seed")
+    }
+
+    /// Returns the `0x`-prefixed Ethereum address of the EVM signing key
+    pub fn evm_address(&self) -> Result<String, String> {
+        let pub_key = self.evm_public_key_bytes()?;
+        let hash = Keccak256::digest(&pub_key[1..]);
+        Ok(format!("0x{}", hex::encode(&hash[12..])))
+    }
+
+    /// Signs keccak256(message) with the EVM signing key, returning the signature and recovery id.
+    /// The nonce is derived deterministically (RFC 6979), so equal messages yield equal signatures.
+    /// k256 already returns low-S signatures (EIP-2) with the matching recovery id.
+    pub fn sign_message_keccak256(
+        &self,
+        message: &[u8],
+    ) -> Result<(k256::ecdsa::Signature, k256::ecdsa::RecoveryId), String> {
+        trace!(key_source = ?self.source, "Signing message with keccak256");
+        let digest = Keccak256::digest(message);
+        self.evm_signing_key()?
+            .sign_prehash_recoverable(&digest)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result_with_provenance() -> VerificationResult {
+        let located = |transcript, start, end| FieldProvenance { transcript, start, end, authenticated: true };
+        let provenance = [
+            ("score", located(TranscriptDirection::Recv, 120, 123)),
+            ("request_line", located(TranscriptDirection::Sent, 0, 18)),
+            ("host", located(TranscriptDirection::Sent, 20, 37)),
+        ]
+        .into_iter()
+        .map(|(name, provenance)| (name.to_string(), provenance))
+        .collect();
+        VerificationResult {
+            is_valid: true,
+            server_name: "example.com".to_string(),
+            score: "742".to_string(),
+            verifying_key: "02ab".to_string(),
+            sent_hex_encoded: String::new(),
+            sent_readable: String::new(),
+            recv_hex_encoded: String::new(),
+            recv_readable: String::new(),
+            time: "2026-01-01T00:00:00+00:00".to_string(),
+            result_id: String::new(),
+            response_status: 200,
+            provenance,
+            required_min_score: None,
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn provenance_serializes_identically_after_a_json_round_trip() {
+        let result = result_with_provenance();
+        let json = serde_json::to_string(&result).unwrap();
+        let round_tripped: VerificationResult = serde_json::from_str(&json).unwrap();
+        let json_again = serde_json::to_string(&round_tripped).unwrap();
+        assert_eq!(Sha256::digest(json.as_bytes()), Sha256::digest(json_again.as_bytes()));
+        // Keys are written in sorted order, independent of insertion or hashing order
+        let host = json.find("\"host\"").unwrap();
+        let request_line = json.find("\"request_line\"").unwrap();
+        let score = json.find("\"score\":{").unwrap();
+        assert!(host < request_line && request_line < score, "{}", json);
+    }
+
+    #[test]
+    fn keccak256_signatures_are_low_s_and_recover_the_signer() {
+        let key_material = KeyMaterial::new_random();
+        let expected = *key_material.evm_signing_key().unwrap().verifying_key();
+        for i in 0..64u32 {
+            let message = format!("message {}", i);
+            let (signature, recovery_id) = key_material.sign_message_keccak256(message.as_bytes()).unwrap();
+            assert!(signature.normalize_s().is_none(), "high-S signature for {:?}", message);
+            let digest = Keccak256::digest(message.as_bytes());
+            let recovered =
+                k256::ecdsa::VerifyingKey::recover_from_prehash(&digest, &signature, recovery_id).unwrap();
+            assert_eq!(recovered, expected);
+        }
+    }
+
+    /// Signs `message` twice in `mode`, with the key material and with a copy restored from its bytes
+    fn sign_twice(mode: SigningMode, message: &[u8]) -> [Vec<u8>; 3] {
+        let key_material = KeyMaterial::new_random();
+        let restored = KeyMaterial::from_random_key_bytes(&key_material.signing_key.to_bytes()).unwrap();
+        let sign = |key_material: KeyMaterial| match mode {
+            SigningMode::P256Sha256 | SigningMode::Ed25519 => {
+                let key_material = key_material.with_scheme(if mode == SigningMode::Ed25519 {
+                    SignatureScheme::Ed25519
+                } else {
+                    SignatureScheme::P256
+                });
+                assert_eq!(SigningMode::for_scheme(key_material.scheme), mode);
+                [key_material.sign_message(message), key_material.sign_message(message)]
+            }
+            SigningMode::Keccak256Recoverable => [(); 2].map(|_| {
+                let (signature, recovery_id) = key_material.sign_message_keccak256(message).unwrap();
+                let mut bytes = signature.to_bytes().to_vec();
+                bytes.push(recovery_id.to_byte());
+                bytes
+            }),
+        };
+        let [first, second] = sign(key_material);
+        let [restored_signature, _] = sign(restored);
+        [first, second, restored_signature]
+    }
+
+    #[test]
+    fn signatures_are_deterministic_in_every_signing_mode() {
+        for mode in [SigningMode::P256Sha256, SigningMode::Keccak256Recoverable, SigningMode::Ed25519] {
+            let [first, second, restored] = sign_twice(mode, b"0x1234abcd");
+            assert_eq!(first, second, "{:?} signatures differ for the same message", mode);
+            assert_eq!(first, restored, "{:?} signatures differ for the same key", mode);
+        }
+    }
+
+    #[test]
+    fn signatures_differ_for_different_messages() {
+        let key_material = KeyMaterial::new_random();
+        assert_ne!(key_material.sign_message(b"a"), key_material.sign_message(b"b"));
+        let ed25519 = KeyMaterial::new_random().with_scheme(SignatureScheme::Ed25519);
+        assert_ne!(ed25519.sign_message(b"a"), ed25519.sign_message(b"b"));
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
-use crate::types::{KeyMaterial};
-use sha2::{Digest, Sha512};
+use crate::types::{KeyMaterial, ReportDataHashAlgorithm};
+use sha2::{Digest, Sha256, Sha512};
+use sha3::Keccak256;
+use tracing::{debug, trace};
 
 /// Encodes a UTF-8 message string into its hexadecimal representation
 pub fn encode_message_hex(
@@ -8,27 +10,46 @@ pub fn encode_message_hex(
     return hex::encode(message)
 }
 
-/// Signs a hex-encoded message string using the provided `KeyMaterial`
+/// Signs a hex-encoded message string using the provided `KeyMaterial` in its signature scheme
 /// and returns the signature as a hex string
 pub fn sign_message(
     key_material: &KeyMaterial,
     message_hex: &str,
 ) -> String {
-    println!("[sign_message] Signing message: {}", message_hex);
-    let signature = key_material.sign_message(message_hex.as_bytes());
-    let signature_bytes = signature.to_bytes();
+    trace!(message_hex, "Signing message");
+    let signature_bytes = key_material.sign_message(message_hex.as_bytes());
     let signature_hex_encoded = hex::encode(signature_bytes);
-    println!("[sign_message] Signature generated: {}", signature_hex_encoded);
+    trace!(signature = %signature_hex_encoded, "Signature generated");
     return signature_hex_encoded;
 }
 
-/// Computes a report hash (SHA-512) of the public key to embed in attestation
+/// Signs a hex-encoded message string over its keccak256 digest with the EVM signing key
+/// and returns the 65-byte `r || s || v` signature as a hex string together with the recovery id
+pub fn sign_message_keccak256(
+    key_material: &KeyMaterial,
+    message_hex: &str,
+) -> Result<(String, u8), String> {
+    trace!(message_hex, "Signing message with keccak256");
+    let (signature, recovery_id) = key_material.sign_message_keccak256(message_hex.as_bytes())?;
+    let mut signature_bytes = signature.to_bytes().to_vec();
+    signature_bytes.push(27 + recovery_id.to_byte()); // Ethereum-style `v`
+    let signature_hex_encoded = hex::encode(signature_bytes);
+    trace!(signature = %signature_hex_encoded, "Signature generated");
+    Ok((signature_hex_encoded, recovery_id.to_byte()))
+}
+
+/// Computes the report_data hash of the given data with the configured algorithm
 pub fn prepare_report_data(
     data : &str,
+    algorithm: ReportDataHashAlgorithm,
 ) -> String {
-    // Convert the input data to a SHA-512 hash
-    let hash = Sha512::digest(data.as_bytes());
-    println!("[prepare_report_data] SHA-512 hash computed: {}", hex::encode(hash));
+    // Hash the input data with the selected algorithm
+    let hash = match algorithm {
+        ReportDataHashAlgorithm::Sha512 => Sha512::digest(data.as_bytes()).to_vec(),
+        ReportDataHashAlgorithm::Sha256 => Sha256::digest(data.as_bytes()).to_vec(),
+        ReportDataHashAlgorithm::Keccak256 => Keccak256::digest(data.as_bytes()).to_vec(),
+    };
+    debug!(algorithm = ?algorithm, hash = %hex::encode(&hash), "Report data hash computed");
     // Return the hex-encoded hash as a string
     format!("0x{}", hex::encode(hash))
 }
\ No newline at end of file
@@ -1,177 +1,1922 @@
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
 use regex::Regex;
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
 use std::time::Instant;
+use tlsn_core::presentation::Presentation;
+use tlsn_core::signing::{KeyAlgId, VerifyingKey};
+use tlsn_core::transcript::{Idx, PartialTranscript};
 use tlsn_core::CryptoProvider;
+use std::ops::Range;
+use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use encoding_rs::{Encoding, UTF_8};
+use rustls_pki_types::{CertificateDer, ServerName};
+use webpki::EndEntityCert;
+use tracing::debug;
 
 use crate::config;
-use crate::types::{PresentationJSON, VerificationError, VerificationResult};
+use crate::crypto_provider;
+use crate::deadline::Deadline;
+use crate::json_path;
+use crate::metrics;
+use crate::quote;
+use crate::response_schema;
+use crate::server_names;
+use crate::server_policy::{self, ServerPolicy};
+use crate::types::{FieldProvenance, VerificationTimings, MultipleHostHeadersPolicy, PipelineCheck, PipelineStage, ServerNameSource, TranscriptDirection, PresentationJSON, VerificationError, VerificationResult};
 
 /// Verifies a TLSNotary presentation proof from JSON string input
 ///
+/// The cryptographic verification and the extraction of the server name, transcript, request
+/// and score always run. The non-cryptographic checks run in the order configured with
+/// `TLSN_VERIFIER_PIPELINE`, each at the first stage where its inputs are available.
+///
 /// # Arguments
 ///
 /// * `json` - A string slice containing a TLSNotary presentation in JSON format.
+/// * `provider` - CryptoProvider holding the hash and signature algorithms to verify with.
+/// * `deadline` - Overall request deadline, checked before the expensive steps.
 ///
 /// # Returns
 ///
 /// * `Ok(VerificationResult)` if the proof is valid and passes all checks
 /// * `Err(VerificationError)` if any verification step fails
-pub fn verify_proof(json: &str) -> Result<VerificationResult, VerificationError> {
+pub fn verify_proof(
+    json: &str,
+    provider: &CryptoProvider,
+    deadline: &Deadline,
+) -> Result<VerificationResult, VerificationError> {
     let total_start = Instant::now(); // Track total verification time
+    let pipeline = config::get_verification_pipeline();
 
-    println!("[{}] ⏱ Starting verification...", chrono::Utc::now());
+    debug!("Starting verification");
 
     // Step 1: Parse JSON into PresentationJSON struct
     let start = Instant::now();
     let presentation_json =
         PresentationJSON::from_json_str(json).map_err(|e| VerificationError {
+            code: None,
             message: format!("Invalid JSON format: {}", e),
         })?;
-    println!("✅ JSON parsed in {:?}", start.elapsed());
+    let parse_time = start.elapsed();
+    debug!(elapsed_us = parse_time.as_micros() as u64, "JSON parsed");
 
-    // Step 2: Check for expected TLSNotary core version
-    let expected_version = config::get_tlsn_core_version();
-    if presentation_json.version != expected_version {
-        return Err(VerificationError {
-            message: format!(
-                "Version mismatch: expected '{}', got '{}'",
-                expected_version, presentation_json.version
-            ),
-        });
-    }
+    // Step 2: Run the checks that only need the presentation JSON (e.g. the version)
+    run_stage(pipeline, PipelineStage::Parsed, |check| match check {
+        PipelineCheck::Version => check_version(&presentation_json.version),
+        other => unreachable!("{:?} does not run at the parsed stage", other),
+    })?;
 
     // Step 3: Convert presentation_json -> Presentation object
+    check_deadline(deadline, "decoding")?;
     let start = Instant::now();
-    let presentation = presentation_json
-        .to_presentation()
-        .map_err(|e| VerificationError {
-            message: format!("Invalid presentation encoding: {}", e),
-        })?;
-    println!("✅ Presentation decoded in {:?}", start.elapsed());
+    let presentation = presentation_json.to_presentation()?;
+    let decode_time = start.elapsed();
+    debug!(elapsed_us = decode_time.as_micros() as u64, "Presentation decoded");
 
     // Step 4: Ensure verifying key exists
     let verifying_key = presentation.verifying_key().data.clone();
     if verifying_key.is_empty() {
         return Err(VerificationError {
+            code: None,
             message: "Verifying key is empty or missing".to_string(),
         });
     }
 
-    // Step 5: Run cryptographic verification of the presentation
+    // Step 4a: Run the checks of the decoded presentation (notary key, unverified server name)
+    // before the expensive cryptographic verification
+    let server_name_source = config::get_server_name_source();
+    let disclosed_identity = once_cell::unsync::OnceCell::new();
+    let identity = || disclosed_identity.get_or_init(|| DisclosedIdentity::read(&presentation));
+    run_stage(pipeline, PipelineStage::Decoded, |check| match check {
+        PipelineCheck::ServerNameHint => {
+            check_server_name_hint(server_name_source, || identity().server_name.clone())
+        }
+        PipelineCheck::NotaryAttestation => {
+            check_notary_attestation(presentation_json.notary_attestation.as_deref(), &verifying_key)
+        }
+        PipelineCheck::NotaryKeyStrength => check_notary_key_strength(presentation.verifying_key()),
+        other => unreachable!("{:?} does not run at the decoded stage", other),
+    })?;
+
+    // The server certificate is only reachable before `verify` consumes the presentation;
+    // its opening is checked against the attestation by `verify`
+    let leaf_cert = if server_policy::has_cert_pins() || server_name_source != ServerNameSource::Sni {
+        identity().leaf_certificate.clone()
+    } else {
+        None
+    };
+    let leaf_cert_fingerprint = leaf_cert.as_ref().map(|der| hex::encode(Sha256::digest(der)));
+
+    // Step 5: Run cryptographic verification of the presentation (mandatory, not part of the pipeline)
+    check_deadline(deadline, "cryptographic verification")?;
     let start = Instant::now();
-    let pres_out = presentation
-        .verify(&CryptoProvider::default())
-        .map_err(|e| VerificationError {
-            message: format!("Presentation verification failed: {}", e),
-        })?;
-    println!("✅ Presentation verified in {:?}", start.elapsed());
+    let key_alg = presentation.verifying_key().alg;
+    let pres_out = presentation.verify(provider).map_err(|e| {
+        // Point out unsupported algorithms together with what is supported
+        let supported = crypto_provider::supported_algorithms();
+        let message = e.to_string();
+        if !supported.signature.contains(&key_alg.as_u8()) || message.to_lowercase().contains("unsupported") {
+            VerificationError::with_code(
+                "UNSUPPORTED_ALGORITHM",
+                format!(
+                    "Presentation verification failed: {} (notary key algorithm id {}; supported signature \
+                     algorithm ids: {:?}, supported hash algorithm ids: {:?})",
+                    message, key_alg, supported.signature, supported.hash
+                ),
+            )
+        } else {
+            VerificationError {
+                code: None,
+                message: format!("Presentation verification failed: {}", message),
+            }
+        }
+    })?;
+    let verify_time = start.elapsed();
+    debug!(elapsed_us = verify_time.as_micros() as u64, "Presentation verified");
 
-    // Step 6: Validate server name against allowed list
-    let server_name = pres_out
-        .server_name
-        .map(|sn| sn.to_string())
-        .unwrap_or_else(|| "<no server_name>".to_string());
+    // Step 6: Select the server name from the trusted source (SNI, certificate or both agreeing)
+    let sni_name = pres_out.server_name.map(|sn| sn.to_string());
+    let server_name = match server_name_source {
+        ServerNameSource::Sni => sni_name.unwrap_or_else(|| "<no server_name>".to_string()),
+        ServerNameSource::Certificate => {
+            let cert = leaf_cert.as_deref().ok_or_else(missing_certificate_error)?;
+            // Prefer the SNI name if the certificate covers it, otherwise the first accepted name it covers
+            sni_name
+                .filter(|name| certificate_covers(cert, name))
+                .or_else(|| {
+                    config::get_server_names()
+                        .into_iter()
+                        .find(|name| certificate_covers(cert, name))
+                })
+                .ok_or_else(|| {
+                    VerificationError::with_code(
+                        "SNI_CERT_MISMATCH",
+                        "Server certificate is not valid for any accepted server name",
+                    )
+                })?
+        }
+        ServerNameSource::Both => {
+            let cert = leaf_cert.as_deref().ok_or_else(missing_certificate_error)?;
+            let name = sni_name.unwrap_or_else(|| "<no server_name>".to_string());
+            if !certificate_covers(cert, &name) {
+                return Err(VerificationError::with_code(
+                    "SNI_CERT_MISMATCH",
+                    format!("Server certificate is not valid for the SNI name '{}'", name),
+                ));
+            }
+            name
+        }
+    };
 
-    let accepted_server_names = config::get_server_names();
-    if !accepted_server_names.contains(&server_name) {
-        return Err(VerificationError {
-            message: format!("Server name '{}' is not in the accepted list", server_name),
-        });
-    }
+    // Step 6a: Select the verification policy for this server (falls back to global defaults)
+    let policy = server_policy::policy_for(&server_name);
 
     // Step 7: Parse timestamp from connection info
     let secs = pres_out.connection_info.time as i64;
     let naive = NaiveDateTime::from_timestamp_opt(secs, 0).ok_or_else(|| VerificationError {
+        code: None,
         message: "Invalid or missing timestamp".to_string(),
     })?;
     let dt: DateTime<Utc> = Utc.from_utc_datetime(&naive);
 
+    // Step 7a: Run the checks of the verified connection (server name, version, certificate pin,
+    // capture time)
+    run_stage(pipeline, PipelineStage::Verified, |check| match check {
+        PipelineCheck::ServerName => check_server_name(&server_name),
+        PipelineCheck::ServerVersion => check_server_version(&policy, &presentation_json.version, &server_name),
+        PipelineCheck::CertFingerprint => {
+            check_cert_fingerprint(&policy, leaf_cert_fingerprint.as_deref(), &server_name)
+        }
+        PipelineCheck::TimeWindow => check_time_window(dt),
+        other => unreachable!("{:?} does not run at the verified stage", other),
+    })?;
+
     // Step 8: Extract transcript and get sent/received messages
     let mut transcript = pres_out.transcript.ok_or_else(|| VerificationError {
+        code: None,
         message: "Missing transcript in presentation output".to_string(),
     })?;
 
+    // Step 8a: Run the checks of the whole transcript (authenticated fraction and fragmentation)
+    run_stage(pipeline, PipelineStage::Transcript, |check| match check {
+        PipelineCheck::AuthenticatedFraction => check_authenticated_fraction(&transcript),
+        PipelineCheck::TranscriptRegions => check_transcript_regions(&transcript),
+        other => unreachable!("{:?} does not run at the transcript stage", other),
+    })?;
+
     transcript.set_unauthed(b'X'); // Mark unauthenticated region
     let sent_bytes = transcript.sent_unsafe().to_vec();
     let recv_bytes = transcript.received_unsafe().to_vec();
     let sent = String::from_utf8_lossy(&sent_bytes);
     let recv = String::from_utf8_lossy(&recv_bytes);
 
-    println!(
-        "✅ Transcript parsed, sent/recv size = {}/{}",
-        sent_bytes.len(),
-        recv_bytes.len()
-    );
+    debug!(sent_bytes = sent_bytes.len(), recv_bytes = recv_bytes.len(), "Transcript parsed");
 
-    // Step 9: Extract and validate Host header
-    let host_line = sent
-        .lines()
-        .find(|line| line.to_lowercase().starts_with("host:"))
+    // Step 9: Parse the request line (origin-form or absolute-form, HTTP/1.0 or HTTP/1.1)
+    // and extract the Host header. An HTTP/1.0 request may omit Host when its request line
+    // carries an absolute-form URI; the URI's authority is used instead.
+    let request_line = sent.lines().next().ok_or_else(|| VerificationError {
+        code: None,
+        message: "Missing request line in sent transcript".to_string(),
+    })?;
+    let request_line = parse_request_line(request_line)
+        .filter(|line| ACCEPTED_HTTP_VERSIONS.contains(&line.version))
         .ok_or_else(|| VerificationError {
-            message: "Missing 'Host' header in sent transcript".to_string(),
+            code: None,
+            message: "Request line is malformed or uses an unsupported HTTP version".to_string(),
         })?;
-    let host = host_line.trim_start_matches("host:").trim();
+    let host_values: Vec<&str> = sent
+        .lines()
+        .take_while(|line| !line.trim().is_empty()) // Only the header section of the request
+        .filter_map(|line| header_value(line, "host"))
+        .collect();
+    let host_value = match (host_values.first(), request_line.authority) {
+        (Some(host_value), _) => host_value,
+        (None, Some(authority)) if request_line.version == "HTTP/1.0" => authority,
+        _ => {
+            return Err(VerificationError {
+                code: None,
+                message: "Missing 'Host' header in sent transcript".to_string(),
+            });
+        }
+    };
+    let host_port = split_host_port(host_value).1;
+
+    // Step 9a: Run the checks of the request (Host header, headers, path, extra requests)
+    run_stage(pipeline, PipelineStage::Request, |check| match check {
+        PipelineCheck::HostHeader => check_host_header(
+            &request_line,
+            &host_values,
+            host_value,
+            &server_name,
+            config::get_multiple_host_headers_policy(),
+        ),
+        PipelineCheck::HostPort => check_host_port(host_port),
+        PipelineCheck::AuthorizationScheme => check_authorization_scheme(&sent_bytes, transcript.sent_authed()),
+        PipelineCheck::RequiredHeaders => check_required_headers(&sent_bytes, transcript.sent_authed()),
+        PipelineCheck::RequestPath => check_request_path(&request_line, &policy),
+        PipelineCheck::ExtraRequests => check_extra_requests(&sent, config::get_max_transcript_requests()),
+        other => unreachable!("{:?} does not run at the request stage", other),
+    })?;
+
+    // Step 10: Frame the first response.
+    // Only the first response counts: pipelined later responses must not supply the score
+    let first_recv_bytes = &recv_bytes[..first_message_len(&recv_bytes)];
+    let response_status = parse_status_line(first_recv_bytes).ok_or_else(|| VerificationError {
+        code: None,
+        message: "Response status line is missing, malformed or uses an unsupported HTTP version".to_string(),
+    })?;
+
+    // Step 10a: Run the checks of the response (completeness, status, charset, schema, body
+    // timestamp) before trusting any field in it
+    run_stage(pipeline, PipelineStage::Response, |check| match check {
+        PipelineCheck::ResponseComplete => check_response_complete(first_recv_bytes)
+            .map_err(|message| VerificationError::with_code("TRUNCATED_RESPONSE", message)),
+        PipelineCheck::ResponseStatus => check_response_status(response_status),
+        PipelineCheck::ResponseCharset => check_response_charset(first_recv_bytes),
+        PipelineCheck::ResponseSchema => check_response_schema(first_recv_bytes),
+        PipelineCheck::BodyTimestamp => check_body_timestamp(first_recv_bytes, dt),
+        other => unreachable!("{:?} does not run at the response stage", other),
+    })?;
+
+    // Step 11: Extract credit score from response JSON.
+    // Extract the score from the JSON response body with the configured selector: a JSONPath
+    // expression (e.g. `$.scores[?(@.type=="fico")].value`) or a JSON pointer, otherwise the
+    // body's single `"value"` field. The selection must be exactly one number or numeric string.
+    let body = parse_response_json(first_recv_bytes).ok_or_else(|| {
+        VerificationError::with_code(
+            "SCORE_NOT_FOUND",
+            "Credit score cannot be extracted: response body is missing or not valid JSON",
+        )
+    })?;
+    // Two-stage extraction: if the score record is wrapped in a base64-encoded JSON field,
+    // decode it first and apply the score selector to the decoded record
+    let encoded_field = match &policy.score_encoded_pointer {
+        Some(selector) => Some(decode_base64_field(&body, selector)?),
+        None => None,
+    };
+    let score_source = encoded_field.as_ref().map_or(&body, |(_, decoded)| decoded);
+    let _credit_score = extract_score(score_source, policy.score_pointer.as_deref())?;
+    // Member name and value that locate the score in the transcript bytes: the score itself,
+    // or the encoded field holding it, since the decoded record does not appear in the transcript
+    let score_location = match (&encoded_field, &policy.score_encoded_pointer) {
+        (Some((encoded, _)), Some(selector)) => selector_last_key(selector).map(|key| (key, encoded.clone())),
+        _ => policy
+            .score_pointer
+            .as_deref()
+            .map_or(Some(DEFAULT_SCORE_FIELD.to_string()), selector_last_key)
+            .map(|key| (key, _credit_score.clone())),
+    };
+    let score_range = score_location
+        .as_ref()
+        .and_then(|(key, value)| score_byte_range(first_recv_bytes, key, value));
+
+    // Step 11a: Run the checks of the extracted score (critical regions, minimum score)
+    run_stage(pipeline, PipelineStage::Scored, |check| match check {
+        PipelineCheck::CriticalRegions => check_critical_regions(
+            &sent_bytes,
+            &transcript,
+            score_range.as_ref().map(|(field, _)| field.clone()),
+        ),
+        PipelineCheck::MinScore => check_min_score(&policy, &_credit_score, &server_name),
+        other => unreachable!("{:?} does not run at the scored stage", other),
+    })?;
+
+    let total_time = total_start.elapsed();
+    debug!(elapsed_us = total_time.as_micros() as u64, "Verification complete");
+
+    // Step 12: Return result with useful metadata
+    let score = _credit_score;
+    let verifying_key = hex::encode(verifying_key);
+    let time = dt.to_rfc3339();
+    // Mask cookies and other sensitive header values in both the readable and the hex-encoded
+    // transcripts, unless the operator opted in to byte-exact hex
+    let sensitive_headers = config::get_sensitive_headers();
+    let raw_transcript_hex = config::get_raw_transcript_hex();
+    let result_id = VerificationResult::compute_result_id(&server_name, &score, &time, &verifying_key);
+
+    // Record where each extracted field sits in the transcript and whether it is authenticated
+    let request_line_range = 0..find_bytes(&sent_bytes, b"\r\n", 0).unwrap_or(sent_bytes.len());
+    let located_fields = [
+        ("request_line", TranscriptDirection::Sent, Some(request_line_range)),
+        ("host", TranscriptDirection::Sent, find_header_line(&sent_bytes, b"host:")),
+        ("score", TranscriptDirection::Recv, score_range.map(|(_, value)| value)),
+    ];
+    let provenance = located_fields
+        .into_iter()
+        .filter_map(|(name, transcript_direction, range)| {
+            let range = range?;
+            let authed = match transcript_direction {
+                TranscriptDirection::Sent => transcript.sent_authed(),
+                TranscriptDirection::Recv => transcript.received_authed(),
+            };
+            let provenance = FieldProvenance {
+                transcript: transcript_direction,
+                start: range.start,
+                end: range.end,
+                authenticated: is_range_authenticated(authed, &range),
+            };
+            Some((name.to_string(), provenance))
+        })
+        .collect();
+    Ok(VerificationResult {
+        is_valid: true,
+        server_name,
+        score,
+        verifying_key,
+        sent_hex_encoded: transcript_hex(&sent_bytes, &sensitive_headers, raw_transcript_hex),
+        sent_readable: mask_sensitive_headers(&sent, &sensitive_headers),
+        recv_hex_encoded: transcript_hex(&recv_bytes, &sensitive_headers, raw_transcript_hex),
+        recv_readable: mask_sensitive_headers(&recv, &sensitive_headers),
+        time,
+        result_id,
+        response_status,
+        provenance,
+        required_min_score: None,
+        timings: Some(VerificationTimings {
+            parse_ms: metrics::as_millis_f64(parse_time),
+            decode_ms: metrics::as_millis_f64(decode_time),
+            verify_ms: metrics::as_millis_f64(verify_time),
+            total_ms: metrics::as_millis_f64(total_time),
+        }),
+    })
+}
+
+/// Runs the enabled checks of `stage` in the configured order, stopping at the first failure
+fn run_stage(
+    pipeline: &[PipelineCheck],
+    stage: PipelineStage,
+    mut run: impl FnMut(PipelineCheck) -> Result<(), VerificationError>,
+) -> Result<(), VerificationError> {
+    pipeline
+        .iter()
+        .filter(|check| check.stage() == stage)
+        .try_for_each(|check| run(*check))
+}
 
-    if host != server_name {
+/// Checks for an expected TLSNotary core version. The server name is not known yet, so any
+/// version accepted for some server passes here; `check_server_version` checks the server's own list
+fn check_version(version: &str) -> Result<(), VerificationError> {
+    if !server_policy::is_accepted_for_any_server(version) {
         return Err(VerificationError {
+            code: None,
+            message: format!(
+                "Version mismatch: expected {} (or a version a server policy accepts), got '{}'",
+                server_policy::describe_default_accepted_versions(),
+                version
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects presentations whose disclosed SNI name is not accepted before the cryptographic
+/// verification. The name is not verified yet, so `check_server_name` still checks the verified
+/// name; this only fails fast. Skipped when the name comes from the certificate.
+fn check_server_name_hint(
+    source: ServerNameSource,
+    unverified_server_name: impl FnOnce() -> Option<String>,
+) -> Result<(), VerificationError> {
+    if source == ServerNameSource::Certificate {
+        return Ok(());
+    }
+    match unverified_server_name() {
+        Some(name) => check_server_name(&name),
+        None => Ok(()),
+    }
+}
+
+/// Ensures the notary's verifying key is bound in its own attestation quote
+fn check_notary_attestation(quote_hex: Option<&str>, verifying_key: &[u8]) -> Result<(), VerificationError> {
+    match quote_hex {
+        Some(quote_hex) => verify_notary_attestation(quote_hex, verifying_key),
+        None if config::get_require_notary_attestation() => Err(VerificationError::with_code(
+            "NOTARY_ATTESTATION_REQUIRED",
+            "Presentation must include a notary attestation",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Validates the server name against the accepted list, compared in IDNA ASCII form so
+/// Unicode and punycode spellings match
+fn check_server_name(server_name: &str) -> Result<(), VerificationError> {
+    if !server_names::is_accepted(server_name) {
+        return Err(VerificationError {
+            code: None,
+            message: format!("Server name '{}' is not in the accepted list", server_name),
+        });
+    }
+    Ok(())
+}
+
+/// Ensures the presentation version is accepted for this server
+fn check_server_version(policy: &ServerPolicy, version: &str, server_name: &str) -> Result<(), VerificationError> {
+    if !policy.accepts_version(version) {
+        return Err(VerificationError::with_code(
+            "VERSION_NOT_ACCEPTED",
+            format!("Presentation version '{}' is not accepted for {}", version, server_name),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensures the server's leaf certificate matches a pinned fingerprint (if pinned),
+/// so a mis-issued certificate for the right name is still rejected
+fn check_cert_fingerprint(
+    policy: &ServerPolicy,
+    fingerprint: Option<&str>,
+    server_name: &str,
+) -> Result<(), VerificationError> {
+    if !policy.accepts_cert_fingerprint(fingerprint) {
+        return Err(VerificationError::with_code(
+            "CERT_FINGERPRINT_MISMATCH",
+            format!(
+                "Server certificate fingerprint {} is not pinned for {}",
+                fingerprint.unwrap_or("(no certificate disclosed)"),
+                server_name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensures the proof was captured inside the accepted time-of-day window (if configured)
+fn check_time_window(dt: DateTime<Utc>) -> Result<(), VerificationError> {
+    match config::get_accepted_time_window() {
+        Some((start, end)) => check_time_window_in(dt, start, end, config::get_accepted_time_window_tz()),
+        None => Ok(()),
+    }
+}
+
+/// Ensures `dt`, read as wall-clock time in `tz`, falls inside the window `[start, end)`
+fn check_time_window_in(dt: DateTime<Utc>, start: NaiveTime, end: NaiveTime, tz: Tz) -> Result<(), VerificationError> {
+    let local_time = dt.with_timezone(&tz).time();
+    if !is_within_time_window(local_time, start, end) {
+        return Err(VerificationError::with_code(
+            "PROOF_OUTSIDE_WINDOW",
+            format!(
+                "Proof captured at {} ({}) is outside the accepted window {}-{}",
+                local_time.format("%H:%M:%S"),
+                tz,
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensures enough of the transcript is authenticated (if configured)
+fn check_authenticated_fraction(transcript: &PartialTranscript) -> Result<(), VerificationError> {
+    let min_fraction = config::get_min_authenticated_fraction();
+    let total_len = transcript.len_sent() + transcript.len_received();
+    let authed_len = transcript.sent_authed().len() + transcript.received_authed().len();
+    let authed_fraction = if total_len == 0 { 0.0 } else { authed_len as f64 / total_len as f64 };
+    if authed_fraction < min_fraction {
+        return Err(VerificationError::with_code(
+            "INSUFFICIENT_AUTHENTICATION",
+            format!(
+                "Only {:.1}% of the transcript is authenticated, at least {:.1}% required",
+                authed_fraction * 100.0,
+                min_fraction * 100.0
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Counts the maximal runs of authenticated and of unauthenticated bytes in a transcript
+/// direction of `len` bytes, i.e. one more than the number of transitions between them
+fn count_regions(authed: &Idx, len: usize) -> usize {
+    let mut regions = 0;
+    let mut pos = 0;
+    for range in authed.iter_ranges() {
+        let range = range.start.min(len)..range.end.min(len);
+        if range.is_empty() {
+            continue;
+        }
+        if range.start > pos {
+            regions += 2; // An unauthenticated gap, then this authenticated range
+        } else if regions == 0 {
+            regions += 1; // The transcript starts authenticated
+        } // Otherwise the range continues the previous authenticated region
+        pos = pos.max(range.end);
+    }
+    if pos < len {
+        regions += 1; // Trailing unauthenticated region
+    }
+    regions
+}
+
+/// Ensures the transcript is not chopped into too many authenticated islands (if configured).
+/// Such proofs are valid but suspicious and expensive to reason about.
+fn check_transcript_regions(transcript: &PartialTranscript) -> Result<(), VerificationError> {
+    let Some(max_regions) = config::get_max_transcript_regions() else {
+        return Ok(());
+    };
+    let regions = count_regions(transcript.sent_authed(), transcript.len_sent())
+        + count_regions(transcript.received_authed(), transcript.len_received());
+    if regions > max_regions {
+        return Err(VerificationError::with_code(
+            "TOO_MANY_REDACTIONS",
+            format!(
+                "Transcript is split into {} authenticated and unauthenticated regions, at most {} allowed",
+                regions, max_regions
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns whether two `host[:port]` values name the same origin: the hosts compare equal in
+/// IDNA ASCII form (so case does not matter) and so do the ports, a missing port meaning 443
+fn same_host_and_port(a: &str, b: &str) -> bool {
+    let ((a_host, a_port), (b_host, b_port)) = (split_host_port(a), split_host_port(b));
+    normalize_domain(a_host) == normalize_domain(b_host) && a_port.unwrap_or(443) == b_port.unwrap_or(443)
+}
+
+/// Ensures the Host header names the server: an absolute-form URI must agree with it,
+/// repeated Host headers are handled per `multiple_hosts_policy` (guarding against request
+/// smuggling), and the host must match the server name. `host_value` is the Host header
+/// value the request is attributed to, including any `:port` suffix.
+fn check_host_header(
+    request_line: &RequestLine,
+    host_values: &[&str],
+    host_value: &str,
+    server_name: &str,
+    multiple_hosts_policy: MultipleHostHeadersPolicy,
+) -> Result<(), VerificationError> {
+    let host = split_host_port(host_value).0;
+    // An absolute-form URI names the target itself, so it must agree with the Host header
+    if let Some(authority) = request_line.authority {
+        if !same_host_and_port(authority, host_value) {
+            return Err(VerificationError::with_code(
+                "AMBIGUOUS_HOST",
+                format!("Request URI authority '{}' does not match Host header '{}'", authority, host_value),
+            ));
+        }
+    }
+
+    if host_values.len() > 1 {
+        let all_agree = host_values.iter().all(|value| same_host_and_port(value, host_value));
+        let allowed = match multiple_hosts_policy {
+            MultipleHostHeadersPolicy::Reject => false,
+            MultipleHostHeadersPolicy::RequireAgreement => all_agree,
+        };
+        if !allowed {
+            return Err(VerificationError::with_code(
+                "AMBIGUOUS_HOST",
+                format!("Sent transcript contains {} Host headers", host_values.len()),
+            ));
+        }
+    }
+
+    if normalize_domain(host) != normalize_domain(server_name) {
+        return Err(VerificationError {
+            code: None,
             message: format!(
                 "Host header '{}' does not match server name '{}'",
                 host, server_name
             ),
         });
     }
+    Ok(())
+}
 
-    // Step 10: Extract the request path and match against expected credit-score endpoint
-    let request_line = sent.lines().next().ok_or_else(|| VerificationError {
-        message: "Missing request line in sent transcript".to_string(),
+/// Checks the Host header's port against the expected port (if configured).
+/// A Host header without a port implies the HTTPS default, 443.
+fn check_host_port(host_port: Option<u16>) -> Result<(), VerificationError> {
+    if let Some(expected_port) = config::get_expected_host_port() {
+        let port = host_port.unwrap_or(443);
+        if port != expected_port {
+            return Err(VerificationError::with_code(
+                "HOST_PORT_MISMATCH",
+                format!("Host header port {} does not match expected port {}", port, expected_port),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Ensures the request carried an Authorization header with the required scheme (if configured).
+/// Only the header name and scheme must be authenticated; the credential itself may be redacted.
+fn check_authorization_scheme(sent_bytes: &[u8], sent_authed: &Idx) -> Result<(), VerificationError> {
+    if let Some(scheme) = config::get_required_authorization_scheme() {
+        let has_scheme = find_header_line(sent_bytes, b"authorization:").is_some_and(|line| {
+            let value_start = line.start + b"authorization:".len();
+            let value = &sent_bytes[value_start..line.end];
+            let leading_ws = value.iter().take_while(|b| **b == b' ' || **b == b'\t').count();
+            let scheme_range = value_start + leading_ws..value_start + leading_ws + scheme.len();
+            let scheme_matches = sent_bytes
+                .get(scheme_range.clone())
+                .is_some_and(|s| s.eq_ignore_ascii_case(scheme.as_bytes()));
+            let followed_by_space = sent_bytes.get(scheme_range.end) == Some(&b' ');
+            scheme_matches
+                && followed_by_space
+                && is_range_authenticated(sent_authed, &(line.start..scheme_range.end))
+        });
+        if !has_scheme {
+            return Err(VerificationError::with_code(
+                "MISSING_AUTHZ",
+                format!("Request is missing an authenticated Authorization header with scheme '{}'", scheme),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Ensures the request carried each required header with its expected value (if configured).
+/// The whole header line must be authenticated, so a redacted value does not count.
+fn check_required_headers(sent_bytes: &[u8], sent_authed: &Idx) -> Result<(), VerificationError> {
+    for (name, expected) in config::get_required_request_headers() {
+        if !has_authenticated_header(sent_bytes, sent_authed, &name, &expected) {
+            return Err(VerificationError::with_code(
+                "MISSING_REQUEST_HEADER",
+                format!("Request is missing an authenticated '{}: {}' header", name, expected),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Matches the request method and path against the server's accepted paths
+fn check_request_path(request_line: &RequestLine, policy: &ServerPolicy) -> Result<(), VerificationError> {
+    if request_line.method != "GET" || !policy.accepts_path(request_line.path) {
+        return Err(VerificationError {
+            code: None,
+            message: "Request path is missing or invalid".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// An HTTP request line, e.g. `GET /score HTTP/1.1`
+static REQUEST_LINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^(?:GET|HEAD|POST|PUT|DELETE|PATCH|OPTIONS|CONNECT|TRACE)\s+\S+\s+HTTP/\d(?:\.\d)?\s*$"#)
+        .expect("request line pattern is a valid regex")
+});
+
+/// Ensures the sent transcript contains at most `max_requests` requests
+fn check_extra_requests(sent: &str, max_requests: usize) -> Result<(), VerificationError> {
+    // Split the transcript into messages by their framing first, so a request pipelined right
+    // after a body (`...{}GET /admin HTTP/1.1`) starts a line of its own
+    let mut request_count = 0;
+    let mut rest = sent.as_bytes();
+    while !rest.is_empty() {
+        let len = first_message_len(rest);
+        request_count += String::from_utf8_lossy(&rest[..len])
+            .lines()
+            .filter(|line| REQUEST_LINE_REGEX.is_match(line))
+            .count();
+        rest = &rest[len..];
+    }
+    if request_count > max_requests {
+        return Err(VerificationError::with_code(
+            "UNEXPECTED_EXTRA_REQUESTS",
+            format!(
+                "Sent transcript contains {} requests, at most {} allowed",
+                request_count, max_requests
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensures the charset declared in the response's `Content-Type` (e.g.
+/// `application/json; charset=iso-8859-1`) is known and in the accepted set. A response without
+/// a declared charset is read as UTF-8. Fails with `UNSUPPORTED_CHARSET` otherwise.
+fn check_response_charset(first_recv: &[u8]) -> Result<(), VerificationError> {
+    let Some(label) = declared_charset(first_recv) else {
+        return Ok(());
+    };
+    let encoding = Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        VerificationError::with_code("UNSUPPORTED_CHARSET", format!("Response declares unknown charset '{}'", label))
     })?;
+    let accepted = config::get_accepted_charsets();
+    if !accepted.contains(&encoding) {
+        return Err(VerificationError::with_code(
+            "UNSUPPORTED_CHARSET",
+            format!(
+                "Response charset '{}' is not accepted, expected one of {:?}",
+                label,
+                accepted.iter().map(|encoding| encoding.name()).collect::<Vec<_>>()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects malformed or partial bodies before trusting any field in them (if a schema is configured)
+fn check_response_schema(first_recv: &[u8]) -> Result<(), VerificationError> {
+    if response_schema::is_configured() {
+        let body = parse_response_json(first_recv).ok_or_else(|| {
+            VerificationError::with_code("SCHEMA_VALIDATION_FAILED", "Response body is missing or not valid JSON")
+        })?;
+        response_schema::validate(&body).map_err(|errors| {
+            VerificationError::with_code(
+                "SCHEMA_VALIDATION_FAILED",
+                format!("Response body does not match the schema: {}", errors.join("; ")),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Ensures the response body's own timestamp is fresh relative to the connection and not in the
+/// future (if configured)
+fn check_body_timestamp(first_recv: &[u8], dt: DateTime<Utc>) -> Result<(), VerificationError> {
+    if let Some(pointer) = config::get_body_timestamp_pointer() {
+        let body = parse_response_json(first_recv).ok_or_else(|| {
+            VerificationError::with_code(
+                "BODY_TIMESTAMP_MISSING",
+                "Response body is missing or not valid JSON",
+            )
+        })?;
+        let body_time = body
+            .pointer(&pointer)
+            .and_then(parse_json_timestamp)
+            .ok_or_else(|| {
+                VerificationError::with_code(
+                    "BODY_TIMESTAMP_MISSING",
+                    format!("No valid timestamp found at '{}' in response body", pointer),
+                )
+            })?;
+        check_body_time(
+            body_time,
+            dt,
+            Utc::now(),
+            config::get_body_timestamp_max_age_secs(),
+            config::get_body_timestamp_max_skew_secs(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks a response body's timestamp: fails with `BODY_STALE` if it is more than `max_age`
+/// seconds older than the connection, or `BODY_TIMESTAMP_IN_FUTURE` if it lies more than
+/// `max_skew` seconds ahead of `now`
+fn check_body_time(
+    body_time: DateTime<Utc>,
+    connection_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    max_age: i64,
+    max_skew: i64,
+) -> Result<(), VerificationError> {
+    let age = connection_time.signed_duration_since(body_time).num_seconds();
+    if age > max_age {
+        return Err(VerificationError::with_code(
+            "BODY_STALE",
+            format!(
+                "Response body timestamp {} is {}s older than the connection (max {}s)",
+                body_time.to_rfc3339(),
+                age,
+                max_age
+            ),
+        ));
+    }
+    let ahead = body_time.signed_duration_since(now).num_seconds();
+    if ahead > max_skew {
+        return Err(VerificationError::with_code(
+            "BODY_TIMESTAMP_IN_FUTURE",
+            format!(
+                "Response body timestamp {} is {}s in the future (max skew {}s)",
+                body_time.to_rfc3339(),
+                ahead,
+                max_skew
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Ensures the critical regions (request line, Host header, score field) are authenticated (if configured)
+fn check_critical_regions(
+    sent_bytes: &[u8],
+    transcript: &PartialTranscript,
+    score_range: Option<Range<usize>>,
+) -> Result<(), VerificationError> {
+    if config::get_require_authenticated_critical_regions() {
+        let request_line_range = 0..find_bytes(sent_bytes, b"\r\n", 0).unwrap_or(sent_bytes.len());
+        let host_range = find_header_line(sent_bytes, b"host:");
+        let critical_regions = [
+            ("request line", Some(request_line_range), transcript.sent_authed()),
+            ("Host header", host_range, transcript.sent_authed()),
+            ("score field", score_range, transcript.received_authed()),
+        ];
+        for (name, range, authed) in critical_regions {
+            let authenticated = range.is_some_and(|range| is_range_authenticated(authed, &range));
+            if !authenticated {
+                return Err(VerificationError::with_code(
+                    "INSUFFICIENT_AUTHENTICATION",
+                    format!("The {} is not fully authenticated", name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Enforces the server's minimum score (if any)
+fn check_min_score(policy: &ServerPolicy, score: &str, server_name: &str) -> Result<(), VerificationError> {
+    if let Some(min_score) = policy.min_score {
+        let score = numeric_score(score)?;
+        if score < min_score as f64 {
+            return Err(VerificationError::with_code(
+                "SCORE_BELOW_MINIMUM",
+                format!("Score {} is below the minimum {} for {}", score, min_score, server_name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reads a field from the first authenticated response of a verification result, e.g. to
+/// correlate presentations by subject. `field` is a JSON pointer or a `$`-rooted JSONPath that
+/// must select exactly one value; strings and numbers are returned as strings.
+pub fn response_field(result: &VerificationResult, field: &str) -> Option<String> {
+    let recv_bytes = hex::decode(&result.recv_hex_encoded).ok()?;
+    let body = parse_response_json(&recv_bytes[..first_message_len(&recv_bytes)])?;
+    let value = if field.starts_with('$') {
+        match json_path::select(&body, field).ok()?.as_slice() {
+            [value] => (*value).clone(),
+            _ => return None,
+        }
+    } else {
+        body.pointer(field)?.clone()
+    };
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Enforces a per-request score threshold chosen by the authenticated caller (e.g. a loan
+/// product's cutoff) and records it in the result next to the score for the decision record.
+/// Fails with `SCORE_BELOW_REQUIRED` if the score is lower, or `SCORE_NOT_NUMERIC` if it is not a number.
+pub fn require_min_score(
+    mut result: VerificationResult,
+    required_min_score: u64,
+) -> Result<VerificationResult, VerificationError> {
+    let score = numeric_score(&result.score)?;
+    if score < required_min_score as f64 {
+        return Err(VerificationError::with_code(
+            "SCORE_BELOW_REQUIRED",
+            format!("Score {} is below the required minimum {}", score, required_min_score),
+        ));
+    }
+    result.required_min_score = Some(required_min_score);
+    Ok(result)
+}
+
+/// Checks whether `time` falls inside `[start, end)`.
+/// Windows with `end` before `start` are treated as spanning midnight.
+fn is_within_time_window(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// Returns the header section of an HTTP message (without the blank line ending it),
+/// or `None` if the header/body separator is missing
+fn message_head(recv: &[u8]) -> Option<String> {
+    let separator = find_bytes(recv, b"\r\n\r\n", 0)?;
+    Some(String::from_utf8_lossy(&recv[..separator]).into_owned())
+}
 
-    let path_regex = Regex::new(
-        r#"GET\s+(?:https?://[^/]+)?(/users/[^/]+/credit-score)\s+HTTP/1\.1"#,
+/// Returns the charset parameter of an HTTP message's `Content-Type` header, lowercased and
+/// unquoted, e.g. `iso-8859-1` for `application/json; charset="ISO-8859-1"`
+fn declared_charset(recv: &[u8]) -> Option<String> {
+    let head = message_head(recv)?;
+    let content_type = head.lines().find_map(|line| header_value(line, "content-type"))?;
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_lowercase())
+    })
+}
+
+/// Extracts the body bytes of an HTTP response, decoding chunked transfer encoding if present.
+/// Returns `None` if the header/body separator is missing.
+fn response_body(recv: &[u8]) -> Option<Vec<u8>> {
+    let separator = find_bytes(recv, b"\r\n\r\n", 0)?;
+    let head = String::from_utf8_lossy(&recv[..separator]);
+    let body = &recv[separator + 4..];
+    let chunked = head.lines().any(|line| {
+        let line = line.to_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    if !chunked {
+        return Some(body.to_vec());
+    }
+
+    // Reassemble the chunks: "<size-hex>\r\n<data>\r\n" ... "0\r\n\r\n"
+    let mut decoded = Vec::new();
+    let mut rest = body;
+    while let Some(line_end) = find_bytes(rest, b"\r\n", 0) {
+        let size_line = String::from_utf8_lossy(&rest[..line_end]);
+        let size_str = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16).ok()?;
+        let after = &rest[line_end + 2..];
+        if size == 0 || after.len() < size {
+            break;
+        }
+        decoded.extend_from_slice(&after[..size]);
+        rest = after[size..].strip_prefix(b"\r\n").unwrap_or(&after[size..]);
+    }
+    Some(decoded)
+}
+
+/// Extracts the body of an HTTP response as text, decoded with the charset declared in its
+/// `Content-Type` (UTF-8 if none is declared or it is unknown)
+fn response_text(recv: &[u8]) -> Option<String> {
+    let body = response_body(recv)?;
+    let encoding = declared_charset(recv)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    Some(encoding.decode(&body).0.into_owned())
+}
+
+/// Parses the body of an HTTP response as JSON. If strict parsing fails and
+/// `TLSN_VERIFIER_LENIENT_RESPONSE_JSON` is on, retries as JSON5 (comments, trailing commas,
+/// unquoted keys) for upstreams that emit non-standard JSON.
+fn parse_response_json(recv: &[u8]) -> Option<Value> {
+    let body = response_text(recv)?;
+    let body = body.trim();
+    serde_json::from_str(body)
+        .ok()
+        .or_else(|| config::get_lenient_response_json().then(|| json5::from_str(body).ok()).flatten())
+}
+
+/// Interprets a JSON value as a timestamp, accepting RFC 3339 strings or Unix seconds
+fn parse_json_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)),
+        Value::Number(n) => Utc.timestamp_opt(n.as_i64()?, 0).single(),
+        _ => None,
+    }
+}
+
+/// Replaces the values of the given headers (case-insensitive) in an HTTP transcript with `***`.
+/// Only header sections are touched: lines from a request/status line up to the next empty line.
+fn mask_sensitive_headers(transcript: &str, sensitive_headers: &[String]) -> String {
+    let mut in_headers = true; // A transcript starts with a request or status line
+    transcript
+        .split_inclusive('\n')
+        .map(|line| {
+            let content = line.trim_end_matches(['\r', '\n']);
+            if content.is_empty() {
+                in_headers = false;
+            } else if content.starts_with("HTTP/") || content.ends_with("HTTP/1.1") || content.ends_with("HTTP/1.0") {
+                in_headers = true; // Start of a further (pipelined) message
+            } else if in_headers {
+                if let Some((name, _)) = content.split_once(':') {
+                    if sensitive_headers.iter().any(|h| h.eq_ignore_ascii_case(name.trim())) {
+                        return format!("{}: ***{}", name, &line[content.len()..]);
+                    }
+                }
+            }
+            line.to_string()
+        })
+        .collect()
+}
+
+/// Overwrites the values of the given headers (case-insensitive) in an HTTP transcript with `*`,
+/// byte for byte, so offsets into the transcript (e.g. in `provenance`) stay valid.
+/// Header sections are found as in `mask_sensitive_headers`.
+fn mask_sensitive_header_bytes(transcript: &[u8], sensitive_headers: &[String]) -> Vec<u8> {
+    let mut masked = Vec::with_capacity(transcript.len());
+    let mut in_headers = true;
+    for line in transcript.split_inclusive(|&b| b == b'\n') {
+        let content_len = line.len() - line.iter().rev().take_while(|&&b| b == b'\r' || b == b'\n').count();
+        let content = &line[..content_len];
+        let start = masked.len();
+        masked.extend_from_slice(line);
+        if content.is_empty() {
+            in_headers = false;
+        } else if content.starts_with(b"HTTP/") || content.ends_with(b"HTTP/1.1") || content.ends_with(b"HTTP/1.0") {
+            in_headers = true;
+        } else if in_headers {
+            if let Some(colon) = content.iter().position(|&b| b == b':') {
+                let name = String::from_utf8_lossy(&content[..colon]);
+                if sensitive_headers.iter().any(|h| h.eq_ignore_ascii_case(name.trim())) {
+                    masked[start + colon + 1..start + content_len].fill(b'*');
+                }
+            }
+        }
+    }
+    masked
+}
+
+/// Hex-encodes a transcript with its sensitive header values masked, or byte-exact if `raw`
+fn transcript_hex(transcript: &[u8], sensitive_headers: &[String], raw: bool) -> String {
+    if raw {
+        hex::encode(transcript)
+    } else {
+        hex::encode(mask_sensitive_header_bytes(transcript, sensitive_headers))
+    }
+}
+
+/// HTTP versions accepted in the request line
+const ACCEPTED_HTTP_VERSIONS: [&str; 2] = ["HTTP/1.0", "HTTP/1.1"];
+
+/// Components of an HTTP request line
+struct RequestLine<'a> {
+    method: &'a str,
+    authority: Option<&'a str>, // `host[:port]` of an absolute-form URI
+    path: &'a str,              // Path and query (`/` if an absolute-form URI has none)
+    version: &'a str,
+}
+
+/// Parses a request line in origin-form (`GET /path HTTP/1.1`) or absolute-form
+/// (`GET https://host/path HTTP/1.0`). Returns `None` if it is malformed, uses a scheme
+/// other than `http`/`https`, or embeds credentials in the URI.
+fn parse_request_line(line: &str) -> Option<RequestLine<'_>> {
+    let mut parts = line.split_whitespace();
+    let (method, target, version) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    if target.starts_with('/') {
+        return Some(RequestLine { method, authority: None, path: target, version });
+    }
+    let (scheme, rest) = target.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    if authority.is_empty() || authority.contains('@') {
+        return None;
+    }
+    let path = if path.is_empty() { "/" } else { path };
+    Some(RequestLine { method, authority: Some(authority), path, version })
+}
+
+/// Parses the status code from the status line at the start of an HTTP response
+/// (`HTTP/1.1 200 OK`; the reason phrase may be empty). Returns `None` if the line is
+/// malformed, uses an unsupported HTTP version or the code is not in `100..=599`.
+fn parse_status_line(data: &[u8]) -> Option<u16> {
+    let line_end = find_bytes(data, b"\r\n", 0)?;
+    let line = std::str::from_utf8(&data[..line_end]).ok()?;
+    let mut parts = line.splitn(3, ' ');
+    let (version, code) = (parts.next()?, parts.next()?);
+    if !ACCEPTED_HTTP_VERSIONS.contains(&version) || code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    code.parse().ok().filter(|code| (100..=599).contains(code))
+}
+
+/// Ensures the proven response succeeded (if configured), so the score is not read from
+/// stray bytes of an error page
+fn check_response_status(status: u16) -> Result<(), VerificationError> {
+    if config::get_require_2xx() && !(200..=299).contains(&status) {
+        return Err(VerificationError::with_code(
+            "UNEXPECTED_RESPONSE_STATUS",
+            format!("Response status {} is not 2xx", status),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the value of a header line if it is a `name` header. The name is matched
+/// case-insensitively (`Host:`, `host:`, `HOST:`) and the spaces and tabs around the value
+/// are removed, so `host:  example.com` and `Host:\texample.com` both yield `example.com`.
+fn header_value<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (line_name, value) = line.split_once(':')?;
+    line_name
+        .eq_ignore_ascii_case(name)
+        .then(|| value.trim_matches(|c| c == ' ' || c == '\t'))
+}
+
+/// Splits an optional `:port` suffix off a Host header value.
+/// Bracketed IPv6 literals (`[::1]:8443`) are supported; an unparsable port is kept as part of the host.
+fn split_host_port(host: &str) -> (&str, Option<u16>) {
+    let port_sep = match host.strip_prefix('[') {
+        Some(rest) => rest.find(']').map(|end| end + 2).filter(|&i| host[i..].starts_with(':')),
+        None => host.rfind(':'),
+    };
+    match port_sep.and_then(|i| host[i + 1..].parse().ok().map(|port| (i, port))) {
+        Some((i, port)) => (&host[..i], Some(port)),
+        None => (host, None),
+    }
+}
+
+/// Normalizes a domain name to its lowercase IDNA ASCII (punycode) form.
+/// Falls back to plain lowercasing if the name is not a valid IDN.
+pub fn normalize_domain(name: &str) -> String {
+    idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_lowercase())
+}
+
+/// Server identity a presentation discloses, read before `verify` consumes the presentation,
+/// so it is not yet trusted. tlsn-core `0.1.0-alpha.10` has no accessors for it, so it is read
+/// from the presentation's serialized form. That serializes the whole presentation and depends
+/// on tlsn-core's serde field names, so it is read at most once per verification, and only
+/// when a check needs it.
+struct DisclosedIdentity {
+    server_name: Option<String>,       // `identity.name`
+    leaf_certificate: Option<Vec<u8>>, // `identity.opening.data.certs[0]` (DER)
+}
+
+impl DisclosedIdentity {
+    fn read(presentation: &Presentation) -> Self {
+        let Ok(serialized) = serde_json::to_value(presentation) else {
+            return DisclosedIdentity { server_name: None, leaf_certificate: None };
+        };
+        let server_name = match serialized.pointer("/identity/name") {
+            Some(Value::String(name)) => Some(name.clone()),
+            Some(Value::Object(variants)) => variants.values().next().and_then(Value::as_str).map(str::to_string),
+            _ => None,
+        };
+        let leaf_certificate = serialized
+            .pointer("/identity/opening/data/certs/0")
+            .and_then(|leaf| serde_json::from_value(leaf.clone()).ok());
+        DisclosedIdentity { server_name, leaf_certificate }
+    }
+}
+
+/// Returns whether a leaf certificate (DER) is valid for the DNS name `name`, matching its
+/// subject alternative names (including wildcards) in IDNA ASCII form
+fn certificate_covers(der: &[u8], name: &str) -> bool {
+    let der = CertificateDer::from(der);
+    let Ok(cert) = EndEntityCert::try_from(&der) else {
+        return false;
+    };
+    ServerName::try_from(normalize_domain(name))
+        .is_ok_and(|server_name| cert.verify_is_valid_for_subject_name(&server_name).is_ok())
+}
+
+/// Error for server name sources that need the certificate when the presentation withholds it
+fn missing_certificate_error() -> VerificationError {
+    VerificationError::with_code(
+        "SNI_CERT_MISMATCH",
+        "Presentation does not disclose the server certificate needed to check the server name",
     )
-    .map_err(|e| VerificationError {
-        message: format!("Regex compilation failed: {}", e),
+}
+
+/// Notary key algorithms whose keys can be checked, with the names used in
+/// `TLSN_VERIFIER_ALLOWED_NOTARY_KEY_ALGORITHMS`
+const NOTARY_KEY_ALGORITHMS: [(KeyAlgId, &str); 2] = [(KeyAlgId::K256, "k256"), (KeyAlgId::P256, "p256")];
+
+/// Checks the notary key's algorithm against the allowed set (by name, or by numeric id for
+/// custom algorithms) and, for known curves, that the key is a valid SEC1 point on that curve.
+/// Fails with `WEAK_NOTARY_KEY` otherwise.
+fn check_notary_key_strength(key: &VerifyingKey) -> Result<(), VerificationError> {
+    let name = NOTARY_KEY_ALGORITHMS
+        .iter()
+        .find(|(id, _)| id.as_u8() == key.alg.as_u8())
+        .map(|(_, name)| *name);
+    let allowed = config::get_allowed_notary_key_algorithms();
+    let is_allowed = allowed
+        .iter()
+        .any(|entry| Some(entry.as_str()) == name || entry.parse::<u8>().ok() == Some(key.alg.as_u8()));
+    if !is_allowed {
+        return Err(VerificationError::with_code(
+            "WEAK_NOTARY_KEY",
+            format!(
+                "Notary key algorithm {} (id {}) is not allowed; allowed: {}",
+                name.unwrap_or("unknown"),
+                key.alg,
+                allowed.join(", ")
+            ),
+        ));
+    }
+    let is_valid_point = match name {
+        Some("k256") => k256::PublicKey::from_sec1_bytes(&key.data).is_ok(),
+        Some("p256") => p256::PublicKey::from_sec1_bytes(&key.data).is_ok(),
+        _ => true, // Custom algorithms are validated by their registered verifier
+    };
+    if !is_valid_point {
+        return Err(VerificationError::with_code(
+            "WEAK_NOTARY_KEY",
+            format!("Notary key is not a valid {} public key", name.unwrap_or("unknown")),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a notary attestation quote binds the presentation's verifying key,
+/// i.e. that its report_data equals SHA-512 of the key bytes
+fn verify_notary_attestation(quote_hex: &str, verifying_key: &[u8]) -> Result<(), VerificationError> {
+    let report_data = quote::report_data_from_hex_quote(quote_hex).map_err(|e| {
+        VerificationError::with_code("INVALID_NOTARY_ATTESTATION", format!("Invalid notary attestation: {}", e))
     })?;
+    let expected = Sha512::digest(verifying_key);
+    if report_data[..] != expected[..] {
+        return Err(VerificationError::with_code(
+            "NOTARY_KEY_NOT_ATTESTED",
+            "Notary attestation report_data does not bind the presentation's verifying key",
+        ));
+    }
+    Ok(())
+}
 
-    let _path = path_regex
-        .captures(request_line)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str())
-        .ok_or_else(|| VerificationError {
-            message: "Request path is missing or invalid".to_string(),
+/// Finds the first occurrence of `needle` in `haystack` at or after `from`
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| pos + from)
+}
+
+/// Returns the length of the first HTTP message in `data`, so pipelined messages that follow
+/// it in the same direction are excluded. The body is bounded by chunked framing or
+/// `Content-Length`; without either (or if truncated) the message runs to the end of `data`.
+fn first_message_len(data: &[u8]) -> usize {
+    match message_framing(data) {
+        Some((head_end, BodyFraming::Chunked)) => chunked_body_end(data, head_end).unwrap_or(data.len()),
+        Some((head_end, BodyFraming::ContentLength(len))) => (head_end + len).min(data.len()),
+        Some((_, BodyFraming::UntilClose)) | None => data.len(),
+    }
+}
+
+/// How the end of an HTTP message body is delimited
+enum BodyFraming {
+    Chunked,              // `Transfer-Encoding: chunked`
+    ContentLength(usize), // `Content-Length` bytes
+    UntilClose,           // Neither: the body runs until the connection closes
+}
+
+/// Parses the header section of the HTTP message at the start of `data`.
+/// Returns where the body starts and how it is delimited, or `None` if the header section is incomplete.
+fn message_framing(data: &[u8]) -> Option<(usize, BodyFraming)> {
+    let head_end = find_bytes(data, b"\r\n\r\n", 0)? + 4;
+    let head = String::from_utf8_lossy(&data[..head_end]).to_lowercase();
+    let header_value = |name: &str| {
+        head.lines()
+            .find_map(|line| line.strip_prefix(name).map(|value| value.trim().to_string()))
+    };
+
+    if header_value("transfer-encoding:").is_some_and(|v| v.contains("chunked")) {
+        return Some((head_end, BodyFraming::Chunked));
+    }
+    let framing = match header_value("content-length:").and_then(|v| v.parse::<usize>().ok()) {
+        Some(len) => BodyFraming::ContentLength(len),
+        None => BodyFraming::UntilClose,
+    };
+    Some((head_end, framing))
+}
+
+/// Walks "<size-hex>\r\n<data>\r\n" chunks from `pos` up to the last chunk and its (optional)
+/// trailers. Returns the end of the chunked body, or `None` if it is malformed or cut short.
+/// Chunk sizes come from the transcript, so a chunk reaching past `data` (or past `usize::MAX`)
+/// ends the walk instead of overflowing.
+fn chunked_body_end(data: &[u8], mut pos: usize) -> Option<usize> {
+    while let Some(line_end) = find_bytes(data, b"\r\n", pos) {
+        let size_line = String::from_utf8_lossy(&data[pos..line_end]);
+        let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16).ok()?;
+        if size == 0 {
+            return find_bytes(data, b"\r\n\r\n", pos).map(|i| i + 4);
+        }
+        pos = line_end.checked_add(2)?.checked_add(size)?.checked_add(2)?;
+        if pos > data.len() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Checks that the HTTP response at the start of `data` is complete: the body holds all
+/// `Content-Length` bytes, or a chunked body ends with its last chunk. Close-delimited bodies
+/// cannot be checked and pass.
+fn check_response_complete(data: &[u8]) -> Result<(), String> {
+    let (head_end, framing) = message_framing(data).ok_or("Response headers are incomplete")?;
+    match framing {
+        BodyFraming::Chunked => chunked_body_end(data, head_end)
+            .map(|_| ())
+            .ok_or_else(|| "Chunked response body ends before its last chunk".to_string()),
+        BodyFraming::ContentLength(len) if data.len() - head_end < len => Err(format!(
+            "Response body has {} bytes, Content-Length announces {}",
+            data.len() - head_end,
+            len
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the byte range of the first header line starting with `prefix` (case-insensitive)
+fn find_header_line(bytes: &[u8], prefix: &[u8]) -> Option<Range<usize>> {
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = find_bytes(bytes, b"\r\n", start).unwrap_or(bytes.len());
+        let line = &bytes[start..end];
+        if line.len() >= prefix.len() && line[..prefix.len()].eq_ignore_ascii_case(prefix) {
+            return Some(start..end);
+        }
+        start = end + 2;
+    }
+    None
+}
+
+/// Returns whether the header section of the first request in `sent_bytes` has a `name` header
+/// (case-insensitive) whose trimmed value equals `expected`, on a fully authenticated line
+fn has_authenticated_header(sent_bytes: &[u8], authed: &Idx, name: &str, expected: &str) -> bool {
+    // Skip the request line, then walk header lines up to the blank line ending the head
+    let mut start = match find_bytes(sent_bytes, b"\r\n", 0) {
+        Some(end) => end + 2,
+        None => return false,
+    };
+    while start < sent_bytes.len() {
+        let end = find_bytes(sent_bytes, b"\r\n", start).unwrap_or(sent_bytes.len());
+        let line = &sent_bytes[start..end];
+        if line.is_empty() {
+            break;
+        }
+        let matches = line.iter().position(|b| *b == b':').is_some_and(|colon| {
+            line[..colon].trim_ascii().eq_ignore_ascii_case(name.as_bytes())
+                && line[colon + 1..].trim_ascii() == expected.as_bytes()
+        });
+        if matches && is_range_authenticated(authed, &(start..end)) {
+            return true;
+        }
+        start = end + 2;
+    }
+    false
+}
+
+/// Returns the byte ranges of the first `"<key>": <value>` member holding `score` in the
+/// received bytes: the whole member and the value alone (without quotes for strings)
+fn score_byte_range(recv_bytes: &[u8], key: &str, score: &str) -> Option<(Range<usize>, Range<usize>)> {
+    let pattern = format!(r#""{}"\s*:\s*("(?:[^"\\]|\\.)*"|-?[0-9][0-9.eE+-]*)"#, regex::escape(key));
+    let captures = regex::bytes::Regex::new(&pattern)
+        .ok()?
+        .captures_iter(recv_bytes)
+        .find(|cap| {
+            cap.get(1)
+                .and_then(|m| serde_json::from_slice::<Value>(m.as_bytes()).ok())
+                .is_some_and(|value| match value {
+                    Value::Number(n) => n.to_string() == score,
+                    Value::String(s) => s == score,
+                    _ => false,
+                })
         })?;
+    let value = captures.get(1)?.range();
+    let value = if recv_bytes[value.start] == b'"' { value.start + 1..value.end - 1 } else { value };
+    Some((captures.get(0)?.range(), value))
+}
 
-    // Step 11: Extract credit score from response JSON
-    let score_regex = Regex::new(r#""value"\s*:\s*(\d+)"#).map_err(|e| VerificationError {
-        message: format!("Regex compilation failed: {}", e),
-    })?;
-
-    /// Extracts the credit score from the received HTML response using a regex pattern.
-    /// If the credit score is not found in the response, returns a `VerificationError`.
-    ///
-    /// # Errors
-    ///
-    /// Returns a `VerificationError` if the credit score value cannot be found in the response.
-    let _credit_score = score_regex
-        .captures(&recv)
-        .and_then(|cap| cap.get(1))
-        .map(|m| m.as_str())
-        .ok_or_else(|| VerificationError {
-            message: "Credit score value is missing from response".to_string(),
+/// Returns the member name selected by the last step of a JSON pointer or `$`-rooted JSONPath
+fn selector_last_key(selector: &str) -> Option<String> {
+    if selector.starts_with('$') {
+        json_path::last_key(selector)
+    } else {
+        selector
+            .rsplit('/')
+            .next()
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+    }
+}
+
+/// Reads the single string selected by `selector` (JSON pointer or `$`-rooted JSONPath) from
+/// `body`, base64-decodes it (standard or URL-safe alphabet, padding optional) and parses the
+/// result as JSON. Returns the encoded string and the decoded value.
+fn decode_base64_field(body: &Value, selector: &str) -> Result<(String, Value), VerificationError> {
+    let selected: Vec<&Value> = if selector.starts_with('$') {
+        json_path::select(body, selector).map_err(|e| VerificationError {
+            code: None,
+            message: format!("Invalid encoded score path: {}", e),
+        })?
+    } else {
+        body.pointer(selector).into_iter().collect()
+    };
+    let encoded = match selected.as_slice() {
+        [Value::String(encoded)] => encoded.clone(),
+        [] => {
+            return Err(VerificationError {
+                code: None,
+                message: format!("Encoded score field is missing from response: {} selects nothing", selector),
+            });
+        }
+        [_] => {
+            return Err(VerificationError::with_code(
+                "INVALID_ENCODED_PAYLOAD",
+                format!("Value selected by {} is not a base64 string", selector),
+            ));
+        }
+        _ => {
+            return Err(VerificationError::with_code(
+                "AMBIGUOUS_SCORE",
+                format!("Encoded score path {} matches {} values in the response", selector, selected.len()),
+            ));
+        }
+    };
+    let trimmed = encoded.trim().trim_end_matches('=');
+    let bytes = STANDARD_NO_PAD
+        .decode(trimmed)
+        .or_else(|_| URL_SAFE_NO_PAD.decode(trimmed))
+        .map_err(|e| {
+            VerificationError::with_code(
+                "INVALID_ENCODED_PAYLOAD",
+                format!("Value selected by {} is not valid base64: {}", selector, e),
+            )
         })?;
+    let decoded = serde_json::from_slice(&bytes).map_err(|e| {
+        VerificationError::with_code(
+            "INVALID_ENCODED_PAYLOAD",
+            format!("Base64 payload selected by {} is not valid JSON: {}", selector, e),
+        )
+    })?;
+    Ok((encoded, decoded))
+}
 
-    println!("✅ Verification complete in {:?}", total_start.elapsed());
+/// Member holding the score when no selector is configured
+const DEFAULT_SCORE_FIELD: &str = "value";
 
-    // Step 12: Return result with useful metadata
-    Ok(VerificationResult {
-        is_valid: true,
-        server_name,
-        score: _credit_score.to_string(),
-        verifying_key: hex::encode(verifying_key),
-        sent_hex_encoded: hex::encode(&sent_bytes),
-        sent_readable: sent.to_string(),
-        recv_hex_encoded: hex::encode(&recv_bytes),
-        recv_readable: recv.to_string(),
-        time: dt.to_rfc3339(),
-    })
+/// Interprets a JSON value as a score, accepting numbers (integers or floats) and strings holding
+/// a number, as `numeric_score` parses them
+fn json_score(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) if numeric_score(s).is_ok() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Selects the score in a response body (or decoded score record) with `score_pointer`: a
+/// `$`-rooted JSONPath or a JSON pointer, otherwise the single `"value"` field at any depth.
+/// Fails with `SCORE_NOT_FOUND` if nothing is selected, `AMBIGUOUS_SCORE` if several values are,
+/// `SCORE_NOT_NUMERIC` if the value is not a number or numeric string, and `INVALID_SCORE_PATH`
+/// if the JSONPath does not parse.
+fn extract_score(source: &Value, score_pointer: Option<&str>) -> Result<String, VerificationError> {
+    let (selected, selector): (Vec<&Value>, String) = match score_pointer {
+        Some(path) if path.starts_with('$') => {
+            let selected = json_path::select(source, path).map_err(|e| {
+                VerificationError::with_code("INVALID_SCORE_PATH", format!("Invalid score path: {}", e))
+            })?;
+            (selected, path.to_string())
+        }
+        Some(pointer) => (source.pointer(pointer).into_iter().collect(), pointer.to_string()),
+        None => (find_fields(source, DEFAULT_SCORE_FIELD), format!("\"{}\" field", DEFAULT_SCORE_FIELD)),
+    };
+    match selected.as_slice() {
+        [value] => json_score(value).ok_or_else(|| {
+            VerificationError::with_code(
+                "SCORE_NOT_NUMERIC",
+                format!("Value selected by {} is not a number or numeric string", selector),
+            )
+        }),
+        [] => Err(VerificationError::with_code(
+            "SCORE_NOT_FOUND",
+            format!("Credit score value is missing from response: {} selects nothing", selector),
+        )),
+        _ if score_pointer.is_none() => Err(VerificationError::with_code(
+            "AMBIGUOUS_SCORE",
+            format!(
+                "Response contains {} score fields; set TLSN_VERIFIER_SCORE_FIELD to select one",
+                selected.len()
+            ),
+        )),
+        _ => Err(VerificationError::with_code(
+            "AMBIGUOUS_SCORE",
+            format!("Score path {} matches {} values in the response", selector, selected.len()),
+        )),
+    }
+}
+
+/// Parses a score for a minimum score comparison. Scores taken from JSON numbers (`742`) and
+/// from quoted numbers (`"742"`, `" 742 "`) parse alike; surrounding whitespace is ignored.
+/// Fails with `SCORE_NOT_NUMERIC` if the score is not a finite number in either form.
+fn numeric_score(score: &str) -> Result<f64, VerificationError> {
+    score
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| {
+            VerificationError::with_code(
+                "SCORE_NOT_NUMERIC",
+                format!("Score '{}' is not a number, so it cannot be compared to a minimum", score),
+            )
+        })
+}
+
+/// Returns every value stored under the member `key`, at any depth of `value`
+fn find_fields<'a>(value: &'a Value, key: &str) -> Vec<&'a Value> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .flat_map(|(name, child)| {
+                let own = (name == key).then_some(child);
+                own.into_iter().chain(find_fields(child, key))
+            })
+            .collect(),
+        Value::Array(items) => items.iter().flat_map(|item| find_fields(item, key)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Checks whether `range` lies entirely within a single authenticated range of `authed`
+fn is_range_authenticated(authed: &Idx, range: &Range<usize>) -> bool {
+    authed
+        .iter_ranges()
+        .any(|r| r.start <= range.start && range.end <= r.end)
+}
+
+/// Fails with `DEADLINE_EXCEEDED` if the request deadline passed before `step`
+fn check_deadline(deadline: &Deadline, step: &str) -> Result<(), VerificationError> {
+    if deadline.is_expired() {
+        return Err(VerificationError::with_code(
+            "DEADLINE_EXCEEDED",
+            format!("Request deadline exceeded before {}", step),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn body_timestamps_must_be_fresh_and_not_in_the_future() {
+        let connection_time = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let now = connection_time + chrono::Duration::minutes(5);
+        let check = |body_time| check_body_time(body_time, connection_time, now, 3600, 60).err().and_then(|e| e.code);
+
+        assert_eq!(check(connection_time), None);
+        assert_eq!(check(connection_time - chrono::Duration::seconds(3600)), None);
+        assert_eq!(check(connection_time - chrono::Duration::seconds(3601)), Some("BODY_STALE".to_string()));
+        // After the connection but before now, e.g. the server's clock is slightly ahead
+        assert_eq!(check(now - chrono::Duration::seconds(1)), None);
+        assert_eq!(check(now + chrono::Duration::seconds(60)), None);
+        assert_eq!(check(now + chrono::Duration::seconds(61)), Some("BODY_TIMESTAMP_IN_FUTURE".to_string()));
+        assert_eq!(check(now + chrono::Duration::days(365)), Some("BODY_TIMESTAMP_IN_FUTURE".to_string()));
+    }
+
+    #[test]
+    fn chunked_body_end_finds_last_chunk() {
+        let body = b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\n\r\nNEXT";
+        assert_eq!(chunked_body_end(body, 0), Some(body.len() - 4));
+    }
+
+    #[test]
+    fn chunked_body_end_rejects_truncated_body() {
+        assert_eq!(chunked_body_end(b"4\r\nWiki\r\n5\r\npe", 0), None);
+        assert_eq!(chunked_body_end(b"4\r\nWiki\r\n", 0), None);
+        assert_eq!(chunked_body_end(b"zz\r\nWiki\r\n0\r\n\r\n", 0), None);
+    }
+
+    #[test]
+    fn chunked_body_end_rejects_overflowing_chunk_size() {
+        let max = format!("{:x}\r\nab\r\n0\r\n\r\n", usize::MAX);
+        assert_eq!(chunked_body_end(max.as_bytes(), 0), None);
+        let near_max = format!("{:x}\r\nab\r\n0\r\n\r\n", usize::MAX - 3);
+        assert_eq!(chunked_body_end(near_max.as_bytes(), 0), None);
+        let too_long = format!("{:x}0\r\nab\r\n0\r\n\r\n", usize::MAX);
+        assert_eq!(chunked_body_end(too_long.as_bytes(), 0), None);
+    }
+
+    fn hm(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    fn utc(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    fn in_window(timestamp: &str, window: (&str, &str), tz: Tz) -> bool {
+        check_time_window_in(utc(timestamp), hm(window.0), hm(window.1), tz).is_ok()
+    }
+
+    #[test]
+    fn time_window_includes_start_and_excludes_end() {
+        let cases = [
+            ("2026-06-01T08:59:59Z", false),
+            ("2026-06-01T09:00:00Z", true),
+            ("2026-06-01T16:59:59Z", true),
+            ("2026-06-01T17:00:00Z", false),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(in_window(timestamp, ("09:00", "17:00"), Tz::UTC), expected, "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn time_window_spanning_midnight() {
+        let cases = [
+            ("2026-06-01T21:59:59Z", false),
+            ("2026-06-01T22:00:00Z", true),
+            ("2026-06-02T00:00:00Z", true),
+            ("2026-06-02T05:59:59Z", true),
+            ("2026-06-02T06:00:00Z", false),
+            ("2026-06-02T12:00:00Z", false),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(in_window(timestamp, ("22:00", "06:00"), Tz::UTC), expected, "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn time_window_follows_dst_offset_changes() {
+        // Europe/Berlin switches from UTC+1 to UTC+2 on 2026-03-29
+        let berlin = Tz::Europe__Berlin;
+        assert!(!in_window("2026-03-28T07:30:00Z", ("09:00", "17:00"), berlin)); // 08:30 CET
+        assert!(in_window("2026-03-28T08:00:00Z", ("09:00", "17:00"), berlin)); // 09:00 CET
+        assert!(in_window("2026-03-30T07:00:00Z", ("09:00", "17:00"), berlin)); // 09:00 CEST
+        assert!(in_window("2026-03-30T14:59:59Z", ("09:00", "17:00"), berlin)); // 16:59:59 CEST
+        assert!(!in_window("2026-03-30T15:00:00Z", ("09:00", "17:00"), berlin)); // 17:00 CEST
+    }
+
+    #[test]
+    fn time_window_in_skipped_and_repeated_dst_hours() {
+        let new_york = Tz::America__New_York;
+        // 02:00-03:00 does not exist on 2026-03-08: clocks jump from 01:59:59 EST to 03:00 EDT
+        assert!(!in_window("2026-03-08T06:59:59Z", ("02:00", "03:00"), new_york));
+        assert!(!in_window("2026-03-08T07:00:00Z", ("02:00", "03:00"), new_york));
+        // 01:00-02:00 happens twice on 2026-11-01, first in EDT, then in EST; both are accepted
+        assert!(in_window("2026-11-01T05:30:00Z", ("01:00", "02:00"), new_york));
+        assert!(in_window("2026-11-01T06:30:00Z", ("01:00", "02:00"), new_york));
+        assert!(!in_window("2026-11-01T07:00:00Z", ("01:00", "02:00"), new_york));
+    }
+
+    #[test]
+    fn split_host_port_cases() {
+        let cases = [
+            ("example.com", ("example.com", None)),
+            ("example.com:443", ("example.com", Some(443))),
+            ("example.com:8443", ("example.com", Some(8443))),
+            ("Example.COM:80", ("Example.COM", Some(80))),
+            ("example.com:", ("example.com:", None)),
+            ("example.com:http", ("example.com:http", None)),
+            ("example.com:70000", ("example.com:70000", None)),
+            ("[::1]", ("[::1]", None)),
+            ("[::1]:8443", ("[::1]", Some(8443))),
+            ("[2001:db8::1]:443", ("[2001:db8::1]", Some(443))),
+            ("[2001:db8::1]", ("[2001:db8::1]", None)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(split_host_port(input), expected, "{}", input);
+        }
+    }
+
+    /// Runs `check_host_header` on a request line and its Host header values, attributing the
+    /// request to the first Host header (or, without one, the URI authority)
+    fn host_check(
+        request_line: &str,
+        host_values: &[&str],
+        server_name: &str,
+        policy: MultipleHostHeadersPolicy,
+    ) -> Result<(), Option<String>> {
+        let request_line = parse_request_line(request_line).unwrap();
+        let host_value = host_values.first().copied().or(request_line.authority).unwrap();
+        check_host_header(&request_line, host_values, host_value, server_name, policy).map_err(|e| e.code)
+    }
+
+    /// Request line, Host header values, server name, policy and the expected error code (if any)
+    type HostCase = (&'static str, &'static [&'static str], &'static str, MultipleHostHeadersPolicy, Result<(), Option<String>>);
+
+    #[test]
+    fn check_host_header_cases() {
+        use MultipleHostHeadersPolicy::{Reject, RequireAgreement};
+        let ambiguous = Err(Some("AMBIGUOUS_HOST".to_string()));
+        let cases: [HostCase; 18] = [
+            // Single Host header against the server name
+            ("GET / HTTP/1.1", &["example.com"], "example.com", Reject, Ok(())),
+            ("GET / HTTP/1.1", &["EXAMPLE.com:443"], "example.com", Reject, Ok(())),
+            ("GET / HTTP/1.1", &["bücher.example"], "xn--bcher-kva.example", Reject, Ok(())),
+            ("GET / HTTP/1.1", &["evil.com"], "example.com", Reject, Err(None)),
+            ("GET / HTTP/1.1", &["example.com.evil.com"], "example.com", Reject, Err(None)),
+            // Repeated Host headers
+            ("GET / HTTP/1.1", &["example.com", "example.com"], "example.com", Reject, ambiguous.clone()),
+            ("GET / HTTP/1.1", &["example.com", "Example.COM:443"], "example.com", RequireAgreement, Ok(())),
+            ("GET / HTTP/1.1", &["example.com", "evil.com"], "example.com", RequireAgreement, ambiguous.clone()),
+            ("GET / HTTP/1.1", &["example.com", "example.com:8443"], "example.com", RequireAgreement, ambiguous.clone()),
+            ("GET / HTTP/1.1", &["example.com:8443", "example.com:8443"], "example.com", RequireAgreement, Ok(())),
+            // Absolute-form URIs must agree with the Host header on host and port
+            ("GET https://example.com/a HTTP/1.1", &["example.com"], "example.com", Reject, Ok(())),
+            ("GET https://EXAMPLE.com:443/a HTTP/1.1", &["example.com"], "example.com", Reject, Ok(())),
+            ("GET https://example.com:8443/a HTTP/1.1", &["example.com:8443"], "example.com", Reject, Ok(())),
+            ("GET https://example.com:8443/a HTTP/1.1", &["example.com"], "example.com", Reject, ambiguous.clone()),
+            ("GET https://evil.com/a HTTP/1.1", &["example.com"], "example.com", Reject, ambiguous.clone()),
+            ("GET https://example.com/a HTTP/1.1", &["example.com", "example.com"], "example.com", RequireAgreement, Ok(())),
+            // HTTP/1.0 without a Host header is attributed to the URI authority
+            ("GET https://example.com/a HTTP/1.0", &[], "example.com", Reject, Ok(())),
+            ("GET https://evil.com/a HTTP/1.0", &[], "example.com", Reject, Err(None)),
+        ];
+        for (request_line, host_values, server_name, policy, expected) in cases {
+            assert_eq!(
+                host_check(request_line, host_values, server_name, policy),
+                expected,
+                "{} / {:?} / {:?}",
+                request_line,
+                host_values,
+                policy
+            );
+        }
+    }
+
+    fn extra_requests_code(sent: &str, max_requests: usize) -> Result<(), Option<String>> {
+        check_extra_requests(sent, max_requests).map_err(|e| e.code)
+    }
+
+    #[test]
+    fn check_extra_requests_counts_pipelined_requests() {
+        let single = "GET /score HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let pipelined = "GET /score HTTP/1.1\r\nHost: example.com\r\n\r\nGET /admin HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let with_body = "POST /score HTTP/1.1\r\nHost: example.com\r\nContent-Length: 2\r\n\r\n{}\
+                         DELETE /account HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let extra = Err(Some("UNEXPECTED_EXTRA_REQUESTS".to_string()));
+        assert_eq!(extra_requests_code(single, 1), Ok(()));
+        assert_eq!(extra_requests_code(pipelined, 1), extra);
+        assert_eq!(extra_requests_code(pipelined, 2), Ok(()));
+        assert_eq!(extra_requests_code(with_body, 1), extra);
+        // A request line smuggled into a header value is not at the start of a line
+        let in_header = "GET /score HTTP/1.1\r\nHost: example.com\r\nX-Note: GET /admin HTTP/1.1\r\n\r\n";
+        assert_eq!(extra_requests_code(in_header, 1), Ok(()));
+    }
+
+    #[test]
+    fn first_message_len_stops_before_interleaved_messages() {
+        let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                        7\r\n{\"a\":1}\r\n0\r\n\r\n";
+        let sized = b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\n{\"a\":2}";
+        let next = b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\n{\"a\":3}";
+        for first in [&chunked[..], &sized[..]] {
+            let transcript = [first, next].concat();
+            assert_eq!(first_message_len(&transcript), first.len());
+        }
+        // A chunk whose data looks like a response is still part of the first message
+        let nested = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                       13\r\nHTTP/1.1 200 OK\r\n\r\n\r\n0\r\n\r\n";
+        assert_eq!(first_message_len(&[&nested[..], next].concat()), nested.len());
+        // Without framing the first message runs to the end
+        let close_delimited = b"HTTP/1.1 200 OK\r\n\r\n{\"a\":1}";
+        let transcript = [&close_delimited[..], next].concat();
+        assert_eq!(first_message_len(&transcript), transcript.len());
+    }
+
+    #[test]
+    fn chunked_body_end_in_interleaved_transcript() {
+        // Two chunked responses back to back: each walk ends at its own last chunk
+        let first = b"4\r\nWiki\r\n0\r\n\r\n";
+        let second = b"5\r\npedia\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        let transcript = [&first[..], &second[..]].concat();
+        assert_eq!(chunked_body_end(&transcript, 0), Some(first.len()));
+        assert_eq!(chunked_body_end(&transcript, first.len()), Some(transcript.len()));
+        // A chunk size that is not hex ends the walk
+        assert_eq!(chunked_body_end(b"zz\r\nWiki\r\n0\r\n\r\n", 0), None);
+    }
+
+    #[test]
+    fn numeric_score_cases() {
+        let cases = [
+            ("742", Some(742.0)),
+            (" 742 ", Some(742.0)),
+            ("\t742\n", Some(742.0)),
+            ("742.5", Some(742.5)),
+            ("-1", Some(-1.0)),
+            ("+5", Some(5.0)),
+            ("0", Some(0.0)),
+            ("1e3", Some(1000.0)),
+            ("", None),
+            ("   ", None),
+            ("abc", None),
+            ("7 42", None),
+            ("1,000", None),
+            ("1_000", None),
+            ("NaN", None),
+            ("inf", None),
+            ("-infinity", None),
+            ("1e400", None),
+            ("\"742\"", None),
+        ];
+        for (score, expected) in cases {
+            match expected {
+                Some(value) => assert_eq!(numeric_score(score).unwrap(), value, "{:?}", score),
+                None => assert_eq!(
+                    numeric_score(score).unwrap_err().code.as_deref(),
+                    Some("SCORE_NOT_NUMERIC"),
+                    "{:?}",
+                    score
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn header_value_cases() {
+        let cases = [
+            ("Host: example.com", Some("example.com")),
+            ("host:example.com", Some("example.com")),
+            ("HOST:  example.com  ", Some("example.com")),
+            ("Host:\texample.com\t", Some("example.com")),
+            ("Host: example.com:8443", Some("example.com:8443")),
+            ("Host:", Some("")),
+            ("X-Host: example.com", None),
+            ("Hostname: example.com", None),
+            ("Host example.com", None),
+            (" Host: example.com", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(header_value(line, "host"), expected, "{:?}", line);
+        }
+        // Only the first colon separates the name, so values may contain colons
+        assert_eq!(header_value("Location: https://example.com:8443/a", "location"), Some("https://example.com:8443/a"));
+    }
+
+    #[test]
+    fn set_cookie_is_masked_in_readable_and_hex_transcripts() {
+        let recv = b"HTTP/1.1 200 OK\r\nSet-Cookie: session=s3cr3t-token\r\nContent-Length: 2\r\n\r\n{}";
+        let sensitive = vec!["set-cookie".to_string()];
+
+        let readable = mask_sensitive_headers(&String::from_utf8_lossy(recv), &sensitive);
+        let hex_encoded = transcript_hex(recv, &sensitive, false);
+        let decoded = hex::decode(&hex_encoded).unwrap();
+        assert!(!readable.contains("s3cr3t-token"));
+        assert!(!String::from_utf8_lossy(&decoded).contains("s3cr3t-token"));
+        assert!(!hex_encoded.contains(&hex::encode("s3cr3t-token")));
+        // Masking keeps the length, so byte offsets into the transcript stay valid
+        assert_eq!(decoded.len(), recv.len());
+        assert!(decoded.ends_with(b"Content-Length: 2\r\n\r\n{}"));
+
+        // Byte-exact hex is an explicit opt-in
+        assert_eq!(transcript_hex(recv, &sensitive, true), hex::encode(recv));
+    }
+
+    #[test]
+    fn extract_score_cases() {
+        let body = json!({
+            "value": 742,
+            "report": {"creditScore": "712.5", "grade": "A+", "detail": {"score": 650}},
+            "scores": [{"type": "fico", "value": 700}, {"type": "vantage", "value": 690}],
+        });
+        let cases: [(Option<&str>, Result<&str, &str>); 11] = [
+            (Some("/value"), Ok("742")),
+            (Some("/report/creditScore"), Ok("712.5")),
+            (Some("$.report.detail.score"), Ok("650")),
+            (Some("$.scores[0].value"), Ok("700")),
+            (Some("/report/grade"), Err("SCORE_NOT_NUMERIC")),
+            (Some("/report/detail"), Err("SCORE_NOT_NUMERIC")),
+            (Some("/report/missing"), Err("SCORE_NOT_FOUND")),
+            (Some("$.report.missing"), Err("SCORE_NOT_FOUND")),
+            (Some("$.scores[*].value"), Err("AMBIGUOUS_SCORE")),
+            (Some("$.scores["), Err("INVALID_SCORE_PATH")),
+            (None, Err("AMBIGUOUS_SCORE")),
+        ];
+        for (selector, expected) in cases {
+            match (extract_score(&body, selector), expected) {
+                (Ok(score), Ok(expected)) => assert_eq!(score, expected, "{:?}", selector),
+                (Err(e), Err(code)) => assert_eq!(e.code.as_deref(), Some(code), "{:?}", selector),
+                (result, expected) => panic!("{:?}: got {:?}, expected {:?}", selector, result.map_err(|e| e.code), expected),
+            }
+        }
+        // The default selector finds a single nested "value" field, and still requires a number
+        assert_eq!(extract_score(&json!({"data": {"value": "742"}}), None).unwrap(), "742");
+        assert_eq!(
+            extract_score(&json!({"data": {"value": "high"}}), None).unwrap_err().code.as_deref(),
+            Some("SCORE_NOT_NUMERIC")
+        );
+        assert_eq!(extract_score(&json!({"score": 1}), None).unwrap_err().code.as_deref(), Some("SCORE_NOT_FOUND"));
+    }
 }